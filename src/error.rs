@@ -0,0 +1,125 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use datafusion::error::DataFusionError;
+
+/// The failures specific to this crate. SQL users see them as `Execution error: ` followed by
+/// the message below; embedding code gets the variant back with [`RegexpExtractError::find`].
+#[derive(Debug)]
+pub enum RegexpExtractError
+{
+    /// An argument had a type the function does not accept.
+    WrongArgType{ field: String, got: String },
+    /// The pattern does not compile. `source` is the engine's own error, boxed so that this
+    /// type is the same whichever engine feature is enabled.
+    InvalidPattern{ pattern: String, source: Box<dyn std::error::Error + Send + Sync> },
+    /// The group index is beyond the pattern's capture groups, with `GroupOutOfRange::Error`.
+    GroupOutOfRange{ group: usize, pattern: String, groups: usize },
+    /// The group index does not fit in `usize`.
+    GroupTooLarge(u64),
+    /// The group index is negative.
+    NegativeGroup(i64),
+    /// The pattern is empty, which would match `""` at the start of every row.
+    EmptyPattern,
+    /// The flags string has a character that is not a flag of this function.
+    UnknownFlag(char),
+    /// `start_position` is below 1.
+    InvalidStartPosition(i64),
+    /// Two array arguments have different lengths; positions are 1-based.
+    RowCountMismatch{ argument: usize, rows: usize, first: usize, first_rows: usize },
+    /// A single match ran past the configured time budget.
+    MatchTimeout{ pattern: String, budget: Duration },
+    /// The engine gave up on a match, such as a backtracking engine reaching its step limit.
+    MatchFailed{ pattern: String, source: Box<dyn std::error::Error + Send + Sync> }
+}
+
+impl fmt::Display for RegexpExtractError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            RegexpExtractError::WrongArgType{ field, got } =>
+                write!(f, "Wrong columnar value type expected {} got {}", field, got),
+            RegexpExtractError::InvalidPattern{ pattern, source } =>
+                write!(f, "Invalid regex pattern '{}': {}", pattern, source),
+            RegexpExtractError::GroupOutOfRange{ group, pattern, groups } =>
+                write!(f, "Group index {} out of range for pattern '{}' with {} groups", group, pattern, groups),
+            RegexpExtractError::GroupTooLarge(group) =>
+                write!(f, "Group index {} does not fit in usize", group),
+            RegexpExtractError::NegativeGroup(group) =>
+                write!(f, "Group index {} must not be negative", group),
+            RegexpExtractError::EmptyPattern =>
+                write!(f, "Empty regex pattern is not allowed"),
+            RegexpExtractError::UnknownFlag(flag) =>
+                write!(f, "Unknown regex flag '{}'", flag),
+            RegexpExtractError::InvalidStartPosition(position) =>
+                write!(f, "start_position must be at least 1, got {}", position),
+            RegexpExtractError::RowCountMismatch{ argument, rows, first, first_rows } =>
+                write!(f, "Argument {} has {} rows but argument {} has {}", argument, rows, first, first_rows),
+            RegexpExtractError::MatchTimeout{ pattern, budget } =>
+                write!(f, "Matching pattern '{}' took longer than {:?}", pattern, budget),
+            RegexpExtractError::MatchFailed{ pattern, source } =>
+                write!(f, "Matching pattern '{}' failed: {}", pattern, source)
+        }
+    }
+}
+
+impl std::error::Error for RegexpExtractError
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+    {
+        match self {
+            RegexpExtractError::InvalidPattern{ source, .. } | RegexpExtractError::MatchFailed{ source, .. } =>
+                Some(source.as_ref()),
+            _ => None
+        }
+    }
+}
+
+impl RegexpExtractError
+{
+    pub(crate) fn invalid_pattern<E>(pattern: &str, source: E) -> RegexpExtractError
+        where E: std::error::Error + Send + Sync + 'static
+    {
+        RegexpExtractError::InvalidPattern{ pattern: pattern.to_string(), source: Box::new(source) }
+    }
+
+    /// The `RegexpExtractError` behind `error`, if it was raised by this crate, looking through
+    /// the context and wrapping DataFusion adds on the way out of a query.
+    pub fn find(error: &DataFusionError) -> Option<&RegexpExtractError>
+    {
+        error.iter().find_map(|error| {
+            let mut source = std::error::Error::source(error);
+            while let Some(error) = source
+            {
+                if let Some(found) = error.downcast_ref::<RegexpExtractError>()
+                {
+                    return Some(found);
+                }
+                if let Some(error) = error.downcast_ref::<DataFusionError>()
+                {
+                    return RegexpExtractError::find(error);
+                }
+                if let Some(error) = error.downcast_ref::<Arc<DataFusionError>>()
+                {
+                    return RegexpExtractError::find(error);
+                }
+                source = error.source();
+            }
+            None
+        })
+    }
+}
+
+impl From<RegexpExtractError> for DataFusionError
+{
+    /// The first error of the collection gives the message its `Execution error: ` prefix, as
+    /// `exec_err!` did; the second carries the typed error for [`RegexpExtractError::find`].
+    fn from(error: RegexpExtractError) -> Self
+    {
+        DataFusionError::Collection(vec![
+            DataFusionError::Execution(error.to_string()),
+            DataFusionError::External(Box::new(error)),
+        ])
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use datafusion::common::plan_err;
+use datafusion::error::Result;
+use datafusion::execution::context::{FunctionFactory, RegisterFunction, SessionContext, SessionState};
+use datafusion::logical_expr::{CreateFunction, ScalarUDF};
+use crate::{register_regexp_extract_all_udf, register_regexp_extract_first_of_udf, register_regexp_extract_fixed_udf, register_regexp_extract_named_udf, register_regexp_extract_span_udf, register_regexp_extract_template_udf, register_regexp_extract_udf, register_regexp_group_count_udf, register_regexp_instr_end_udf, register_regexp_matches_udf};
+
+/// Every UDF this crate provides, with default configuration.
+pub fn all_udfs() -> Vec<ScalarUDF>
+{
+    vec![
+        register_regexp_extract_udf(),
+        register_regexp_extract_all_udf(),
+        register_regexp_extract_fixed_udf(),
+        register_regexp_extract_first_of_udf(),
+        register_regexp_extract_named_udf(),
+        register_regexp_instr_end_udf(),
+        register_regexp_extract_span_udf(),
+        register_regexp_extract_template_udf(),
+        register_regexp_group_count_udf(),
+        register_regexp_matches_udf(),
+        #[cfg(feature = "fancy")]
+        crate::register_regexp_extract_fancy_udf(None),
+    ]
+}
+
+/// Registers every UDF in [`all_udfs`] on `ctx`.
+pub fn register_all_udfs(ctx: &SessionContext)
+{
+    for udf in all_udfs()
+    {
+        ctx.register_udf(udf);
+    }
+}
+
+/// Resolves `CREATE FUNCTION <name> ...` to this crate's UDF of the same name, so SQL scripts
+/// can install the functions themselves once the factory is set with
+/// `SessionContext::with_function_factory`. The declared arguments, return type and body are
+/// not used: the UDF's own signature applies.
+#[derive(Debug, Default)]
+pub struct RegexpFunctionFactory;
+
+#[async_trait]
+impl FunctionFactory for RegexpFunctionFactory
+{
+    async fn create(&self, _state: &SessionState, statement: CreateFunction) -> Result<RegisterFunction>
+    {
+        match all_udfs().into_iter().find(|udf| udf.name() == statement.name) {
+            Some(udf) => Ok(RegisterFunction::Scalar(Arc::new(udf))),
+            None => plan_err!("No regexp function named {}", statement.name)
+        }
+    }
+}
@@ -1,18 +1,185 @@
-use std::any::type_name_of_val;
+use std::any::{Any, type_name_of_val};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::sync::Arc;
-use datafusion::arrow::array::{Array, ArrayRef, StringArray};
-use datafusion::error::{DataFusionError, Result};
-use datafusion::logical_expr::{Volatility, create_udf, ScalarUDF, ScalarFunctionImplementation};
-use datafusion::arrow::datatypes::{ArrowNativeType, DataType};
+use datafusion::arrow::array::{Array, ArrayRef, Int64Array, RunArray, StringArray, StringBuilder, UInt32Array, UInt64Array};
+use datafusion::arrow::compute::take;
+use datafusion::error::Result;
+use datafusion::logical_expr::{Expr, Volatility, ReturnFieldArgs, ScalarUDF, ScalarFunctionArgs, ScalarFunctionImplementation, ScalarUDFImpl, Signature, SimpleScalarUDF, TypeSignature};
+use datafusion::arrow::datatypes::{DataType, Field, FieldRef, Int32Type};
 use datafusion::common::ScalarValue;
+use datafusion::prelude::lit;
 use datafusion::physical_plan::ColumnarValue;
-use regex::Regex;
+
+#[cfg(not(any(feature = "regex", feature = "regex-lite")))]
+compile_error!("enable either the `regex` or the `regex-lite` feature");
+
+/// The regex engine behind every function in this crate, selected by Cargo feature.
+#[cfg(not(feature = "regex-lite"))]
+pub use regex as engine;
+#[cfg(feature = "regex-lite")]
+pub use regex_lite as engine;
+
+use engine::{Captures, Regex, RegexBuilder};
+
+mod error;
+mod function_factory;
+mod regex_cache;
+mod regexp_extract_all;
+#[cfg(feature = "fancy")]
+mod regexp_extract_fancy;
+mod regexp_extract_first_of;
+mod regexp_extract_fixed;
+mod regexp_extract_named;
+mod regexp_extract_span;
+mod regexp_extract_template;
+mod regexp_group_count;
+mod regexp_instr_end;
+mod regexp_matches;
+
+pub use error::RegexpExtractError;
+pub use function_factory::{RegexpFunctionFactory, all_udfs, register_all_udfs};
+pub use regex_cache::{DEFAULT_REGEX_CACHE_CAPACITY, RegexCache};
+pub use regexp_extract_all::{RegexpExtractAll, register_regexp_extract_all_udf};
+#[cfg(feature = "fancy")]
+pub use regexp_extract_fancy::{DEFAULT_BACKTRACK_LIMIT, RegexpExtractFancy, register_regexp_extract_fancy_udf};
+pub use regexp_extract_first_of::{RegexpExtractFirstOf, register_regexp_extract_first_of_udf};
+pub use regexp_extract_fixed::{RegexpExtractFixed, register_regexp_extract_fixed_udf};
+pub use regexp_extract_named::{RegexpExtractNamed, register_regexp_extract_named_udf};
+pub use regexp_extract_span::{RegexpExtractSpan, register_regexp_extract_span_udf};
+pub use regexp_extract_template::{RegexpExtractTemplate, register_regexp_extract_template_udf};
+pub use regexp_group_count::{RegexpGroupCount, register_regexp_group_count_udf};
+pub use regexp_instr_end::{RegexpInstrEnd, register_regexp_instr_end_udf};
+pub use regexp_matches::{RegexpMatches, register_regexp_matches_udf};
+
+/// The group argument, either one index for the whole batch or one per row.
+#[derive(Debug, Clone)]
+enum GroupIndex
+{
+    Scalar(usize),
+    Column(Vec<Option<usize>>)
+}
+
+impl GroupIndex
+{
+    fn at(&self, row: usize) -> Option<usize>
+    {
+        match self {
+            GroupIndex::Scalar(group) => Some(*group),
+            GroupIndex::Column(groups) => groups.get(row).copied().flatten()
+        }
+    }
+
+    fn max(&self) -> Option<usize>
+    {
+        match self {
+            GroupIndex::Scalar(group) => Some(*group),
+            GroupIndex::Column(groups) => groups.iter().flatten().max().copied()
+        }
+    }
+}
+
+/// What `regexp_extract` does when the group index is beyond the pattern's capture groups.
+/// A group that exists but did not take part in a match is never affected by this: it is a
+/// no-match for that row.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GroupOutOfRange
+{
+    /// Yield NULL for every affected row.
+    #[default]
+    Null,
+    /// Fail the batch with an execution error.
+    Error
+}
+
+/// What `regexp_extract` yields for a row the pattern does not match.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NoMatchMode
+{
+    #[default]
+    Empty,
+    Null
+}
+
+/// What `regexp_extract` does with a pattern that does not compile.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorMode
+{
+    /// Treat every row as a no-match.
+    #[default]
+    Ignore,
+    /// Fail the batch with an execution error.
+    Error
+}
+
+/// A transformation applied to each extracted value before it is written out, saving a
+/// `TRIM`/`LOWER`/`UPPER` pass over the result. See [`RegexpExtractBuilder::post_process`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostProcess
+{
+    /// Strip leading and trailing Unicode whitespace.
+    Trim,
+    Lowercase,
+    Uppercase
+}
+
+impl PostProcess
+{
+    fn apply<'s>(&self, value: Cow<'s, str>) -> Cow<'s, str>
+    {
+        match (self, value) {
+            (PostProcess::Trim, Cow::Borrowed(value)) => Cow::Borrowed(value.trim()),
+            (PostProcess::Trim, Cow::Owned(value)) => Cow::Owned(value.trim().to_string()),
+            (PostProcess::Lowercase, value) => Cow::Owned(value.to_lowercase()),
+            (PostProcess::Uppercase, value) => Cow::Owned(value.to_uppercase())
+        }
+    }
+}
+
+/// Every policy knob of `regexp_extract` in one place; the default is the behaviour of
+/// [`register_regexp_extract_udf`]. See [`register_regexp_extract_with_options`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegexpExtractOptions
+{
+    pub on_no_match: NoMatchMode,
+    pub on_invalid_pattern: ErrorMode,
+    pub group_out_of_range: GroupOutOfRange
+}
+
+fn group_from_u64(group: u64) -> Result<usize>
+{
+    usize::try_from(group)
+        .map_err(|_| RegexpExtractError::GroupTooLarge(group).into())
+}
+
+/// Checked, unlike `as`: on 32-bit targets a large index must not wrap into a valid group.
+fn group_from_i64(group: i64) -> Result<usize>
+{
+    match u64::try_from(group) {
+        Ok(group) => group_from_u64(group),
+        Err(_) => Err(RegexpExtractError::NegativeGroup(group).into())
+    }
+}
 
 struct RegexpExtractRequest
 {
-    input: Option<StringArray>,
+    /// Kept as the `ArrayRef` handed to the UDF; only ever set to a `StringArray` or a
+    /// run-end encoded array of Utf8 values.
+    input: Option<ArrayRef>,
     regex: Option<String>,
-    group: Option<i64>
+    group: Option<GroupIndex>,
+    flags: Option<String>,
+    /// Characters skipped at the start of every row before matching.
+    start: usize,
+    anchored: bool,
+    multi_line: bool,
+    ignore_whitespace: bool,
+    group_out_of_range: GroupOutOfRange,
+    on_no_match: NoMatchMode,
+    on_invalid_pattern: ErrorMode,
+    post_process: Vec<PostProcess>,
+    cache: Option<Arc<RegexCache>>
 }
 
 impl RegexpExtractRequest
@@ -23,6 +190,16 @@ impl RegexpExtractRequest
             input: None,
             regex: None,
             group: None,
+            flags: None,
+            start: 0,
+            anchored: false,
+            multi_line: false,
+            ignore_whitespace: false,
+            group_out_of_range: GroupOutOfRange::default(),
+            on_no_match: NoMatchMode::default(),
+            on_invalid_pattern: ErrorMode::default(),
+            post_process: Vec::new(),
+            cache: None,
         }
     }
 
@@ -30,88 +207,961 @@ impl RegexpExtractRequest
     {
         match (value, field) {
             (ColumnarValue::Array(array), "input") =>
-                self.input = array.as_any().downcast_ref::<StringArray>().cloned(),
+                self.input = (array.as_any().is::<StringArray>() || utf8_runs(array).is_some()).then(|| array.clone()),
             (ColumnarValue::Scalar(ScalarValue::Utf8(Some(string))), "regex") =>
                 self.regex = Some(string.to_string()),
             (ColumnarValue::Scalar(ScalarValue::Int64(Some(i))), "group") =>
-                self.group = Some(*i),
+                self.group = Some(GroupIndex::Scalar(group_from_i64(*i)?)),
+            (ColumnarValue::Scalar(ScalarValue::UInt64(Some(u))), "group") =>
+                self.group = Some(GroupIndex::Scalar(group_from_u64(*u)?)),
+            (ColumnarValue::Scalar(ScalarValue::UInt32(Some(u))), "group") =>
+                self.group = Some(GroupIndex::Scalar(group_from_u64(u64::from(*u))?)),
+            (ColumnarValue::Scalar(ScalarValue::UInt16(Some(u))), "group") =>
+                self.group = Some(GroupIndex::Scalar(group_from_u64(u64::from(*u))?)),
+            (ColumnarValue::Scalar(ScalarValue::UInt8(Some(u))), "group") =>
+                self.group = Some(GroupIndex::Scalar(group_from_u64(u64::from(*u))?)),
+            (ColumnarValue::Array(array), "group") if array.as_any().is::<UInt64Array>() =>
+            {
+                let groups = array.as_any().downcast_ref::<UInt64Array>().unwrap();
+                self.group = Some(GroupIndex::Column(groups.iter()
+                    .map(|g| g.map(group_from_u64).transpose())
+                    .collect::<Result<Vec<Option<usize>>>>()?));
+            }
+            (ColumnarValue::Array(array), "group") if array.as_any().is::<Int64Array>() =>
+            {
+                let groups = array.as_any().downcast_ref::<Int64Array>().unwrap();
+                self.group = Some(GroupIndex::Column(groups.iter()
+                    .map(|g| g.map(group_from_i64).transpose())
+                    .collect::<Result<Vec<Option<usize>>>>()?));
+            }
+            (ColumnarValue::Scalar(ScalarValue::Utf8(Some(string))), "flags") =>
+                self.flags = Some(string.to_string()),
+            (ColumnarValue::Scalar(ScalarValue::Boolean(Some(case_insensitive))), "flags") =>
+                self.flags = Some(if *case_insensitive { "i" } else { "" }.to_string()),
+            (ColumnarValue::Scalar(ScalarValue::Int64(Some(position))), "start") =>
+                self.start = start_offset(*position)?,
             _ =>
-                return Err(DataFusionError::Execution(format!("Wrong columnar value type expected {} got {}", field, type_name_of_val(value))))
+                return Err(RegexpExtractError::WrongArgType{ field: field.to_string(), got: type_name_of_val(value).to_string() }.into())
         };
         Ok(())
     }
 
+    /// The input one value per row; a run-end encoded input is decoded.
+    fn input(&self) -> Option<Cow<'_, StringArray>>
+    {
+        let input = self.input.as_ref()?;
+        match input.as_any().downcast_ref::<StringArray>() {
+            Some(strings) => Some(Cow::Borrowed(strings)),
+            None => Some(Cow::Owned(utf8_runs(input)?.downcast::<StringArray>()?.into_iter().collect()))
+        }
+    }
+
     pub fn is_usable(&self) -> bool
     {
         self.input.is_some() && self.regex.is_some() && self.group.is_some()
     }
 
-    pub fn fulfill(&self) -> ArrayRef
+    fn compile(&self, regex: &str, flags: &RegexpFlags) -> std::result::Result<Arc<Regex>, engine::Error>
+    {
+        match &self.cache {
+            Some(cache) => cache.get_or_insert_with(&flags.cache_key(regex), || flags.compile(regex)),
+            None => flags.compile(regex).map(Arc::new)
+        }
+    }
+
+    /// Checked once per batch against the compiled pattern rather than on every row.
+    fn check_group(&self, group: &GroupIndex, group_count: usize, regex: &str) -> Result<()>
+    {
+        if let Some(max) = group.max() && max >= group_count
+        {
+            match self.group_out_of_range {
+                GroupOutOfRange::Error =>
+                    return Err(RegexpExtractError::GroupOutOfRange{ group: max, pattern: regex.to_string(), groups: group_count - 1 }.into()),
+                GroupOutOfRange::Null =>
+                    log::debug!("Group index {} out of range for pattern '{}' with {} groups, yielding NULL", max, regex, group_count - 1)
+            }
+        }
+        Ok(())
+    }
+
+    /// The pattern as it will be compiled, after anchoring, and the flags to compile it with.
+    fn prepared_pattern(&self, regex: &str) -> Result<(String, RegexpFlags)>
+    {
+        check_pattern(regex)?;
+        let mut flags = RegexpFlags::parse(self.flags.as_deref().unwrap_or_default())?;
+        flags.multi_line |= self.multi_line;
+        flags.ignore_whitespace |= self.ignore_whitespace;
+        let regex = match (self.anchored, flags.ignore_whitespace) {
+            // A trailing `# comment` would swallow the closing anchor unless it is on a new line.
+            (true, true) => anchor_pattern(&format!("{}\n", regex)),
+            (true, false) => anchor_pattern(regex),
+            (false, _) => regex.to_string()
+        };
+        Ok((regex, flags))
+    }
+
+    /// Compiles a literal pattern while planning, so that no batch has to. Problems with the
+    /// pattern are left for `fulfill` to report.
+    fn precompile(&self)
+    {
+        if let (Some(regex), Some(cache)) = (&self.regex, &self.cache)
+            && let Ok((regex, flags)) = self.prepared_pattern(regex)
+        {
+            let _ = cache.pin_with(&flags.cache_key(&regex), || flags.compile(&regex));
+        }
+    }
+
+    pub fn fulfill(&self) -> Result<ArrayRef>
     {
-        let empty = Arc::new(StringArray::from(vec![""])) as ArrayRef;
+        let rows = self.input.as_ref().map_or(0, |input| input.len());
+        // Only built on the paths that return it, not for every batch.
+        let empty = || Arc::new(StringArray::from(vec![no_match_value(self.on_no_match); rows])) as ArrayRef;
+
+        // Selective filters leave many zero-row batches behind; they need no regex at all.
+        if rows == 0
+        {
+            return Ok(empty());
+        }
 
         if self.is_usable()
         {
-            let input = self.input.clone().unwrap();
-            let regex = self.regex.clone().unwrap();
-            let group_idx = self.group.clone().unwrap().as_usize();
+            let group = self.group.clone().unwrap();
+            let (regex, flags) = self.prepared_pattern(self.regex.as_deref().unwrap())?;
 
-            match Regex::new(regex.as_str())
+            match self.compile(regex.as_str(), &flags)
             {
                 Ok(regexp) =>
                 {
-                    let iter = input.iter().map(|s| {
-                        Some(single_regex_extract(s.unwrap_or_default(), regexp.clone(), group_idx))
-                    });
-                    let out : StringArray = StringArray::from_iter(iter);
-                    Arc::new(out)
+                    self.check_group(&group, regexp.captures_len(), regex.as_str())?;
+
+                    let options = RowOptions{
+                        start: self.start,
+                        trim: flags.trim,
+                        on_no_match: self.on_no_match,
+                        post_process: &self.post_process,
+                    };
+                    match (self.input.as_ref().and_then(utf8_runs), &group) {
+                        // Every row of a run has the same value: extract once per run and repeat.
+                        (Some(runs), GroupIndex::Scalar(_)) =>
+                        {
+                            let values = runs.values().as_any().downcast_ref::<StringArray>().unwrap();
+                            let per_run = extract_rows(values, &regexp, &group, options);
+                            let rows = (0..runs.len() as u32).collect::<Vec<u32>>();
+                            let indices = runs.get_physical_indices(&rows)?.into_iter()
+                                .map(|index| index as u32)
+                                .collect::<UInt32Array>();
+                            Ok(take(&per_run, &indices, None)?)
+                        }
+                        _ => Ok(Arc::new(extract_rows(&self.input().unwrap(), &regexp, &group, options)))
+                    }
                 }
-                Err(_) =>
-                {
-                    empty
+                Err(e) => match self.on_invalid_pattern {
+                    ErrorMode::Error =>
+                        Err(RegexpExtractError::invalid_pattern(&regex, e).into()),
+                    ErrorMode::Ignore =>
+                        Ok(empty())
                 }
             }
         }
         else
         {
-            empty
+            Ok(empty())
+        }
+    }
+}
+
+/// `array` as a run-end encoded array with `Int32` run ends, if its values are Utf8.
+fn utf8_runs(array: &ArrayRef) -> Option<&RunArray<Int32Type>>
+{
+    array.as_any().downcast_ref::<RunArray<Int32Type>>()
+        .filter(|runs| runs.values().data_type() == &DataType::Utf8)
+}
+
+/// The characters to skip for a 1-based `start_position`.
+fn start_offset(position: i64) -> Result<usize>
+{
+    match usize::try_from(position) {
+        Ok(position) if position >= 1 => Ok(position - 1),
+        _ => Err(RegexpExtractError::InvalidStartPosition(position).into())
+    }
+}
+
+/// `input_str` without its first `chars` characters, or `None` if that leaves nothing to
+/// match. Slicing at a `char_indices` offset never splits a multibyte codepoint.
+fn skip_chars(input_str: &str, chars: usize) -> Option<&str>
+{
+    if chars == 0
+    {
+        return Some(input_str);
+    }
+    input_str.char_indices().nth(chars).map(|(offset, _)| &input_str[offset..])
+}
+
+/// An empty pattern matches the empty string at position 0 of every row, which is never what
+/// the caller meant, so it is rejected rather than silently yielding `""` everywhere.
+fn check_pattern(regex: &str) -> Result<()>
+{
+    if regex.is_empty()
+    {
+        return Err(RegexpExtractError::EmptyPattern.into());
+    }
+    Ok(())
+}
+
+/// Wraps `regex` so that it only matches the whole input; the group is non-capturing so
+/// group indices are unchanged.
+fn anchor_pattern(regex: &str) -> String
+{
+    format!(r"\A(?:{})\z", regex)
+}
+
+/// Single-character modifiers accepted by the optional trailing `flags` argument.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct RegexpFlags
+{
+    /// `i`: case-insensitive over the whole pattern, with Unicode mode forced on so non-ASCII
+    /// letters fold too. Like an embedded `(?i)`, this is the regex crate's *simple* case
+    /// folding: `ß` matches `ẞ` but never the two-letter `SS`. Unlike `(?i)` it cannot be
+    /// scoped to part of the pattern, and it still applies if the pattern turns Unicode off.
+    /// Under `regex-lite` case folding is ASCII-only.
+    case_insensitive: bool,
+    /// `m`: `^` and `$` match at line boundaries as well as at the ends of the input. This
+    /// does not let `.` match `\n`.
+    multi_line: bool,
+    /// `o`: restart the search one character after the start of each match instead of at its
+    /// end, so that matches may overlap. Only `regexp_extract_all` accepts it.
+    overlapping: bool,
+    /// `x`: verbose mode. Whitespace in the pattern is ignored and `#` starts a comment running
+    /// to the end of the line, so a space that must match has to be written `\ ` (or `[ ]`,
+    /// `\x20`), and a literal `#` as `\#`.
+    ignore_whitespace: bool,
+    /// `t`: strip leading and trailing Unicode whitespace from each row before matching, so
+    /// that `^` and `$` see the trimmed text. Matches are taken from the trimmed text too.
+    trim: bool
+}
+
+impl RegexpFlags
+{
+    /// The flags of `regexp_extract`, without `o`.
+    fn parse(flags: &str) -> Result<RegexpFlags>
+    {
+        match flags.chars().find(|flag| *flag == 'o') {
+            Some(flag) => Err(RegexpExtractError::UnknownFlag(flag).into()),
+            None => RegexpFlags::parse_all(flags)
+        }
+    }
+
+    /// Every flag, including `o`, which only `regexp_extract_all` can honour.
+    fn parse_all(flags: &str) -> Result<RegexpFlags>
+    {
+        let mut parsed = RegexpFlags::default();
+        for flag in flags.chars()
+        {
+            match flag {
+                'i' => parsed.case_insensitive = true,
+                'm' => parsed.multi_line = true,
+                'o' => parsed.overlapping = true,
+                'x' => parsed.ignore_whitespace = true,
+                't' => parsed.trim = true,
+                _ => return Err(RegexpExtractError::UnknownFlag(flag).into())
+            }
         }
+        Ok(parsed)
+    }
+
+    fn compile(&self, regex: &str) -> std::result::Result<Regex, engine::Error>
+    {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("regex_compile", pattern = regex).entered();
+
+        let mut builder = RegexBuilder::new(regex);
+        if self.case_insensitive
+        {
+            builder.case_insensitive(true);
+            #[cfg(not(feature = "regex-lite"))]
+            builder.unicode(true);
+        }
+        if self.multi_line
+        {
+            builder.multi_line(true);
+        }
+        if self.ignore_whitespace
+        {
+            builder.ignore_whitespace(true);
+        }
+        builder.build()
+    }
+
+    /// Only the flags that change the compiled regex take part in the key.
+    fn cache_key(&self, regex: &str) -> String
+    {
+        let mut key = String::new();
+        if self.case_insensitive
+        {
+            key.push('i');
+        }
+        if self.multi_line
+        {
+            key.push('m');
+        }
+        if self.ignore_whitespace
+        {
+            key.push('x');
+        }
+        key.push('/');
+        key.push_str(regex);
+        key
     }
 }
 
-fn single_regex_extract(input_str: &str, regex: Regex, group: usize) -> String
+/// Extracts one group from many rows against a pattern compiled once. Free of Arrow types, so
+/// through [`extract_each`] the same algorithm backs the UDF, [`regexp_extract_array`] and
+/// [`extract_all`].
+struct RowExtractor<'a>
+{
+    regexp: &'a Regex,
+    group_count: usize,
+    // Empty strings are common (e.g. after COALESCE(col, '')): match "" once and reuse it
+    // instead of running the regex on every such row.
+    empty_captures: Option<Captures<'static>>,
+    /// Returns `None` when the pattern does not match at all.
+    extract: for<'s> fn(&'s str, &Regex, usize) -> Option<&'s str>,
+    on_no_match: NoMatchMode
+}
+
+impl<'a> RowExtractor<'a>
+{
+    fn new(regexp: &'a Regex, on_no_match: NoMatchMode) -> RowExtractor<'a>
+    {
+        RowExtractor{
+            regexp,
+            group_count: regexp.captures_len(),
+            empty_captures: regexp.captures(""),
+            extract: fixed_arity_extractor(regexp),
+            on_no_match,
+        }
+    }
+
+    /// NULL for a group beyond the pattern's groups, otherwise the extracted group, `""` if
+    /// the group did not take part in the match, or the no-match value. A NULL input is
+    /// treated as `""`, a NULL group as no-match. The result borrows from `input`.
+    fn extract<'s>(&self, input: Option<&'s str>, group: Option<usize>) -> Option<&'s str>
+    {
+        match (group, input.unwrap_or_default()) {
+            (Some(group_idx), _) if group_idx >= self.group_count => None,
+            (Some(group_idx), "") => match &self.empty_captures {
+                Some(cap) => Some(cap.get(group_idx).map_or("", |m| m.as_str())),
+                None => no_match_value(self.on_no_match)
+            },
+            (Some(group_idx), s) => (self.extract)(s, self.regexp, group_idx).or_else(|| no_match_value(self.on_no_match)),
+            (None, _) => no_match_value(self.on_no_match)
+        }
+    }
+
+    /// What [`RowExtractor::extract`] returns for a row the pattern cannot match.
+    fn no_match(&self, group: Option<usize>) -> Option<&'static str>
+    {
+        match group {
+            Some(group_idx) if group_idx >= self.group_count => None,
+            _ => no_match_value(self.on_no_match)
+        }
+    }
+}
+
+fn no_match_value(on_no_match: NoMatchMode) -> Option<&'static str>
+{
+    match on_no_match {
+        NoMatchMode::Empty => Some(""),
+        NoMatchMode::Null => None
+    }
+}
+
+/// Per-row settings of [`extract_each`] besides the pattern and group.
+#[derive(Clone, Copy)]
+struct RowOptions<'a>
+{
+    /// Characters skipped at the start of every row.
+    start: usize,
+    /// Trim each row before matching.
+    trim: bool,
+    on_no_match: NoMatchMode,
+    post_process: &'a [PostProcess]
+}
+
+impl RowOptions<'_>
+{
+    /// What [`register_regexp_extract_udf`] does with a plain `(input, pattern, group)` call.
+    const DEFAULT: RowOptions<'static> = RowOptions{
+        start: 0,
+        trim: false,
+        on_no_match: NoMatchMode::Empty,
+        post_process: &[],
+    };
+}
+
+/// [`extract_each`] into a `StringArray`.
+fn extract_rows(input: &StringArray, regexp: &Regex, group: &GroupIndex, options: RowOptions) -> StringArray
+{
+    // Extracted groups are slices of the input, copied straight into the output buffer
+    // without an intermediate `String` per row unless post-processing needs one. So the input
+    // rows' bytes bound the output's, and reserving them avoids regrowing the buffer; only
+    // case mapping can lengthen a value, and then it is still a close estimate.
+    let offsets = input.value_offsets();
+    let bytes = (offsets[offsets.len() - 1] - offsets[0]) as usize;
+    let mut builder = StringBuilder::with_capacity(input.len(), bytes);
+    extract_each(input.iter(), regexp, group, options, |value| builder.append_option(value));
+    builder.finish()
+}
+
+/// The extraction algorithm, free of Arrow types: passes the result for each row of `input`
+/// to `emit`, in order.
+fn extract_each<'a, I, F>(mut input: I, regexp: &Regex, group: &GroupIndex, options: RowOptions, mut emit: F)
+    where I: ExactSizeIterator<Item = Option<&'a str>>, F: FnMut(Option<&str>)
+{
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("regex_match", rows = input.len()).entered();
+
+    let extractor = RowExtractor::new(regexp, options.on_no_match);
+    let extract_row = |s: Option<&'a str>, group: Option<usize>| {
+        let value = match s.map(|s| skip_chars(if options.trim { s.trim() } else { s }, options.start)) {
+            Some(None) => extractor.no_match(group),
+            s => extractor.extract(s.flatten(), group)
+        };
+        value.map(|value| options.post_process.iter().fold(Cow::Borrowed(value), |value, step| step.apply(value)))
+    };
+
+    let rows = input.len();
+    let sample = input.by_ref().take(DEDUP_SAMPLE_ROWS).collect::<Vec<Option<&'a str>>>();
+    let dedup = worth_deduplicating(rows, &sample);
+    let input = sample.into_iter().chain(input);
+    if dedup
+    {
+        // Run the regex once per distinct (value, group) and copy the result to every row.
+        let mut seen = HashMap::new();
+        for (row, s) in input.enumerate()
+        {
+            let group = group.at(row);
+            emit(seen.entry((s, group)).or_insert_with(|| extract_row(s, group)).as_deref());
+        }
+    }
+    else
+    {
+        for (row, s) in input.enumerate()
+        {
+            emit(extract_row(s, group.at(row)).as_deref());
+        }
+    }
+}
+
+/// Rows sampled by [`worth_deduplicating`].
+const DEDUP_SAMPLE_ROWS: usize = 1024;
+
+/// Hashing every row only pays off when values repeat a lot, as after a denormalizing join:
+/// the batch must have at least [`DEDUP_SAMPLE_ROWS`] rows, at most a quarter of which are
+/// distinct among the first [`DEDUP_SAMPLE_ROWS`], given as `sample`.
+fn worth_deduplicating(rows: usize, sample: &[Option<&str>]) -> bool
+{
+    if rows < DEDUP_SAMPLE_ROWS
+    {
+        return false;
+    }
+    let distinct = sample.iter().collect::<HashSet<&Option<&str>>>().len();
+    distinct * 4 <= DEDUP_SAMPLE_ROWS
+}
+
+/// The extraction core on plain Rust values, for callers outside DataFusion. Each output is
+/// `Some("")` for a NULL input or a non-matching row, and `None` if `group` is beyond the
+/// pattern's capture groups. An empty pattern is rejected like in the UDF.
+pub fn extract_all(inputs: &[Option<&str>], pattern: &str, group: usize) -> std::result::Result<Vec<Option<String>>, RegexpExtractError>
+{
+    let regexp = compile_plain(pattern)?;
+    let mut out = Vec::with_capacity(inputs.len());
+    extract_each(inputs.iter().copied(), &regexp, &GroupIndex::Scalar(group), RowOptions::DEFAULT, |value| {
+        out.push(value.map(str::to_string))
+    });
+    Ok(out)
+}
+
+/// Compiles `pattern` for the entry points outside DataFusion, with the UDF's checks.
+fn compile_plain(pattern: &str) -> std::result::Result<Regex, RegexpExtractError>
+{
+    if pattern.is_empty()
+    {
+        return Err(RegexpExtractError::EmptyPattern);
+    }
+    Regex::new(pattern).map_err(|e| RegexpExtractError::invalid_pattern(pattern, e))
+}
+
+/// The 1-based character position of `byte_offset`, which must lie on a char boundary (as
+/// every `Match` offset does).
+fn char_position(input_str: &str, byte_offset: usize) -> i64
+{
+    debug_assert!(input_str.is_char_boundary(byte_offset), "byte offset {} is not a char boundary", byte_offset);
+    input_str[..byte_offset].chars().count() as i64 + 1
+}
+
+/// Patterns whose groups all take part in every match can use `Captures::extract` and skip
+/// the per-group `Option` handling; anything else falls back to [`single_regex_extract`].
+/// `static_captures_len` alone is not enough: `(a)|(b)` always has two groups taking part, but
+/// `extract` returns them by participation, not by index, so every group must participate.
+/// Callers guarantee `group < regex.captures_len()`.
+fn fixed_arity_extractor(regex: &Regex) -> for<'s> fn(&'s str, &Regex, usize) -> Option<&'s str>
+{
+    if regex.static_captures_len() != Some(regex.captures_len())
+    {
+        return single_regex_extract;
+    }
+    match regex.static_captures_len() {
+        Some(2) => fixed_regex_extract::<1>,
+        Some(3) => fixed_regex_extract::<2>,
+        Some(4) => fixed_regex_extract::<3>,
+        Some(5) => fixed_regex_extract::<4>,
+        _ => single_regex_extract
+    }
+}
+
+fn fixed_regex_extract<'s, const N: usize>(input_str: &'s str, regex: &Regex, group: usize) -> Option<&'s str>
 {
     regex.captures(input_str)
-        .and_then(|cap| cap.get(group))
-        .map(|m| m.as_str().to_string())
-        .unwrap_or_default()
+        .map(|cap| {
+            let (whole, groups) = cap.extract::<N>();
+            if group == 0 { whole } else { groups[group - 1] }
+        })
+}
+
+/// The number of capture groups in `pattern`, counting group 0 (the whole match), so that the
+/// valid `group` arguments are `0..capture_count(pattern)`.
+pub fn capture_count(pattern: &str) -> Result<usize>
+{
+    Regex::new(pattern)
+        .map(|regexp| regexp.captures_len())
+        .map_err(|e| RegexpExtractError::invalid_pattern(pattern, e).into())
 }
 
+/// Runs `regexp_extract` over an Arrow array directly, without going through DataFusion.
+/// `pattern` is compiled once for the whole array; unlike the UDF, an invalid pattern is an
+/// error rather than an empty result.
+pub fn regexp_extract_array(input: &StringArray, pattern: &str, group: usize) -> Result<StringArray>
+{
+    let regexp = compile_plain(pattern)?;
+    Ok(extract_rows(input, &regexp, &GroupIndex::Scalar(group), RowOptions::DEFAULT))
+}
+
+/// [`extract_all`] over a slice without NULLs, for callers and tests that have no Arrow
+/// arrays at hand. `Some("")` is a no-match, `None` a group beyond the pattern's groups.
+pub fn extract_all_from(inputs: &[&str], pattern: &str, group: usize) -> Result<Vec<Option<String>>>
+{
+    let inputs = inputs.iter().map(|s| Some(*s)).collect::<Vec<Option<&str>>>();
+    Ok(extract_all(&inputs, pattern, group)?)
+}
 
+/// `None` only when the pattern does not match: a group that captured the empty string, like
+/// `(a*)` against `"bbb"`, is a real `Some("")`.
+fn single_regex_extract<'s>(input_str: &'s str, regex: &Regex, group: usize) -> Option<&'s str>
+{
+    regex.captures(input_str)
+        .map(|cap| cap.get(group).map_or("", |m| m.as_str()))
+}
+
+
+
+/// The number of rows a call produces: the length shared by every array argument, or
+/// `number_rows` when every argument is a scalar. Arrays of different lengths cannot be
+/// lined up row by row and are an error.
+fn batch_rows(args: &[ColumnarValue], number_rows: usize) -> Result<usize>
+{
+    let mut rows = None;
+    for (position, arg) in args.iter().enumerate()
+    {
+        if let ColumnarValue::Array(array) = arg
+        {
+            match rows {
+                None => rows = Some((position, array.len())),
+                Some((first, len)) if len != array.len() =>
+                    return Err(RegexpExtractError::RowCountMismatch{
+                        argument: position + 1,
+                        rows: array.len(),
+                        first: first + 1,
+                        first_rows: len,
+                    }.into()),
+                Some(_) => {}
+            }
+        }
+    }
+    Ok(rows.map_or(number_rows, |(_, len)| len))
+}
+
+/// `(input, pattern, group)`, optionally followed by a flags string or a `case_insensitive`
+/// boolean, for each accepted group index type.
+fn regexp_extract_signatures() -> Vec<TypeSignature>
+{
+    let mut signatures = Vec::new();
+    for group in [DataType::Int64, DataType::UInt64]
+    {
+        signatures.push(TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, group.clone()]));
+        for options in [DataType::Utf8, DataType::Boolean]
+        {
+            signatures.push(TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, group.clone(), options]));
+        }
+    }
+    signatures
+}
+
+/// Which request field an argument after the group sets: an `Int64` is the start position,
+/// anything else the flags.
+fn trailing_option(data_type: &DataType) -> &'static str
+{
+    match data_type {
+        DataType::Int64 => "start",
+        _ => "flags"
+    }
+}
+
+fn regexp_extract_signature() -> Signature
+{
+    Signature::one_of(regexp_extract_signatures(), Volatility::Immutable)
+}
 
 pub fn regexp_extract_impl_to_udf(regexp_extract_impl: ScalarFunctionImplementation) -> ScalarUDF {
-    create_udf("regexp_extract",
-               vec![DataType::Utf8, DataType::Utf8, DataType::Int64],
-               DataType::Utf8, Volatility::Immutable, regexp_extract_impl)
+    ScalarUDF::from(SimpleScalarUDF::new_with_signature("regexp_extract",
+                                                         regexp_extract_signature(),
+                                                         DataType::Utf8, regexp_extract_impl))
 }
 
 
 
-pub fn register_regexp_extract_udf() -> ScalarUDF
+/// Group extracted when `regexp_extract` is called with only an input and a pattern.
+pub const DEFAULT_GROUP: i64 = 1;
+
+/// `regexp_extract(input, pattern [, group [, flags | case_insensitive]])`, configured by
+/// [`RegexpExtractBuilder`]. The group defaults to [`DEFAULT_GROUP`]; a `true` boolean is the
+/// same as the `i` flag.
+///
+/// `regexp_extract(input, pattern, group [, flags], start_position)` only matches from the
+/// 1-based character `start_position` on, like `REGEXP_SUBSTR`; past the end of a row there
+/// is no match.
+#[derive(Debug)]
+pub struct RegexpExtract
+{
+    signature: Signature,
+    config: RegexpExtractBuilder,
+    cache: Arc<RegexCache>
+}
+
+impl RegexpExtract
 {
-    let regexp_extract_impl: ScalarFunctionImplementation;
+    pub fn new() -> RegexpExtract
+    {
+        RegexpExtract::from_builder(RegexpExtractBuilder::new())
+    }
+
+    pub fn from_builder(config: RegexpExtractBuilder) -> RegexpExtract
+    {
+        let mut signatures = vec![TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8])];
+        signatures.extend(regexp_extract_signatures());
+        for group in [DataType::Int64, DataType::UInt64]
+        {
+            signatures.push(TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, group.clone(), DataType::Int64]));
+            for options in [DataType::Utf8, DataType::Boolean]
+            {
+                signatures.push(TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, group.clone(), options, DataType::Int64]));
+            }
+        }
+        // Run-end encoded Utf8 input is accepted as it is, without decoding it first.
+        let runs = DataType::RunEndEncoded(
+            Arc::new(Field::new("run_ends", DataType::Int32, false)),
+            Arc::new(Field::new("values", DataType::Utf8, true)));
+        let encoded = signatures.iter()
+            .filter_map(|signature| match signature {
+                TypeSignature::Exact(types) =>
+                    Some(TypeSignature::Exact(std::iter::once(runs.clone()).chain(types[1..].iter().cloned()).collect())),
+                _ => None
+            })
+            .collect::<Vec<TypeSignature>>();
+        signatures.extend(encoded);
+        RegexpExtract{
+            signature: Signature::one_of(signatures, Volatility::Immutable),
+            cache: Arc::new(RegexCache::new(config.cache_capacity)),
+            config,
+        }
+    }
+}
+
+impl RegexpExtract
+{
+    /// The compiled-pattern cache shared by every batch this instance evaluates.
+    pub fn cache(&self) -> &RegexCache
+    {
+        &self.cache
+    }
 
-    regexp_extract_impl = Arc::new(|args: &[ColumnarValue]|-> Result<ColumnarValue>
+    fn request(&self) -> RegexpExtractRequest
     {
         let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
-        request.set("input", &args[0])?;
-        request.set("regex", &args[1])?;
-        request.set("group", &(args[2]))?;
+        request.anchored = self.config.anchored;
+        request.multi_line = self.config.multi_line;
+        request.ignore_whitespace = self.config.ignore_whitespace;
+        request.group_out_of_range = self.config.options.group_out_of_range;
+        request.on_no_match = self.config.options.on_no_match;
+        request.on_invalid_pattern = self.config.options.on_invalid_pattern;
+        request.post_process = self.config.post_process.clone();
+        request.cache = Some(self.cache.clone());
+        request
+    }
+}
 
-        Ok(ColumnarValue::Array(request.fulfill()))
-    });
+impl Default for RegexpExtract
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+/// Literals as they would be written in SQL, anything else by its usual schema name.
+fn readable_arg(arg: &Expr) -> String
+{
+    match arg {
+        Expr::Literal(ScalarValue::Utf8(Some(s)), _) => format!("'{}'", s.replace('\'', "''")),
+        Expr::Literal(value, _) => value.to_string(),
+        arg => arg.schema_name().to_string()
+    }
+}
 
+impl ScalarUDFImpl for RegexpExtract
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        &self.config.name
+    }
+
+    fn display_name(&self, args: &[Expr]) -> Result<String>
+    {
+        self.schema_name(args)
+    }
+
+    /// `regexp_extract(s, '(\d+)', 1)` rather than DataFusion's default
+    /// `regexp_extract(s,Utf8("(\d+)"),Int64(1))`.
+    fn schema_name(&self, args: &[Expr]) -> Result<String>
+    {
+        let args = args.iter().map(readable_arg).collect::<Vec<String>>();
+        Ok(format!("{}({})", self.name(), args.join(", ")))
+    }
 
-    regexp_extract_impl_to_udf(regexp_extract_impl)
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType>
+    {
+        Ok(DataType::Utf8)
+    }
+
+    fn return_field_from_args(&self, args: ReturnFieldArgs) -> Result<FieldRef>
+    {
+        // A literal pattern is known now, at plan time: compile it once here instead of on
+        // every batch of a long-running query.
+        if let Some(Some(regex)) = args.scalar_arguments.get(1)
+        {
+            let mut request = self.request();
+            let options = args.scalar_arguments.iter().zip(args.arg_fields).skip(3);
+            if request.set("regex", &ColumnarValue::Scalar((*regex).clone())).is_ok()
+                && options.into_iter().all(|(value, field)| {
+                    // The start position does not change the compiled pattern.
+                    trailing_option(field.data_type()) == "start" ||
+                        value.is_some_and(|flags| request.set("flags", &ColumnarValue::Scalar(flags.clone())).is_ok())
+                })
+            {
+                request.precompile();
+            }
+        }
+        Ok(Arc::new(Field::new(self.name(), DataType::Utf8, true)))
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        // With only scalar arguments, e.g. while simplifying constants, one row is enough and
+        // the result stays a scalar.
+        let scalar = args.args.iter().all(|arg| matches!(arg, ColumnarValue::Scalar(_)));
+        let rows = if scalar { 1 } else { batch_rows(&args.args, args.number_rows)? };
+
+        let mut request = self.request();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("regex", &args.args[1])?;
+        match args.args.get(2) {
+            Some(group) => request.set("group", group)?,
+            None => request.set("group", &ColumnarValue::Scalar(ScalarValue::Int64(Some(DEFAULT_GROUP))))?
+        }
+        for option in args.args.iter().skip(3)
+        {
+            request.set(trailing_option(&option.data_type()), option)?;
+        }
+
+        let result = request.fulfill()?;
+        if scalar
+        {
+            Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(&result, 0)?))
+        }
+        else
+        {
+            Ok(ColumnarValue::Array(result))
+        }
+    }
+
+    fn equals(&self, other: &dyn ScalarUDFImpl) -> bool
+    {
+        other.as_any().downcast_ref::<RegexpExtract>()
+            .is_some_and(|other| self.config == other.config)
+    }
+
+    fn hash_value(&self) -> u64
+    {
+        let hasher = &mut DefaultHasher::new();
+        self.name().hash(hasher);
+        self.config.hash(hasher);
+        hasher.finish()
+    }
+}
+
+
+
+/// Builds a `regexp_extract` UDF with non-default matching behaviour.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegexpExtractBuilder
+{
+    name: String,
+    anchored: bool,
+    multi_line: bool,
+    ignore_whitespace: bool,
+    options: RegexpExtractOptions,
+    post_process: Vec<PostProcess>,
+    cache_capacity: usize
+}
+
+impl Default for RegexpExtractBuilder
+{
+    fn default() -> Self
+    {
+        RegexpExtractBuilder{
+            name: "regexp_extract".to_string(),
+            anchored: false,
+            multi_line: false,
+            ignore_whitespace: false,
+            options: RegexpExtractOptions::default(),
+            post_process: Vec::new(),
+            cache_capacity: DEFAULT_REGEX_CACHE_CAPACITY,
+        }
+    }
+}
+
+impl RegexpExtractBuilder
+{
+    pub fn new() -> RegexpExtractBuilder
+    {
+        RegexpExtractBuilder::default()
+    }
+
+    /// The SQL name, `regexp_extract` by default. Each built UDF carries its own options and
+    /// cache, so differently configured instances can be registered side by side under
+    /// different names.
+    pub fn name(mut self, name: impl Into<String>) -> RegexpExtractBuilder
+    {
+        self.name = name.into();
+        self
+    }
+
+    /// Only match when the pattern covers the entire input, as if written `^(?:pattern)$`.
+    pub fn anchored(mut self, anchored: bool) -> RegexpExtractBuilder
+    {
+        self.anchored = anchored;
+        self
+    }
+
+    /// Let `^` and `$` match at every line boundary of a multi-line value. The first match is
+    /// then the one on the first line that matches; lines that do not match are skipped, and
+    /// the result is only a no-match if no line matches. [`RegexpExtractBuilder::anchored`]
+    /// still requires the whole value to match.
+    pub fn multi_line(mut self, multi_line: bool) -> RegexpExtractBuilder
+    {
+        self.multi_line = multi_line;
+        self
+    }
+
+    /// Compile every pattern in verbose mode, as if the `x` flag were always given.
+    pub fn ignore_whitespace(mut self, ignore_whitespace: bool) -> RegexpExtractBuilder
+    {
+        self.ignore_whitespace = ignore_whitespace;
+        self
+    }
+
+    pub fn group_out_of_range(mut self, group_out_of_range: GroupOutOfRange) -> RegexpExtractBuilder
+    {
+        self.options.group_out_of_range = group_out_of_range;
+        self
+    }
+
+    pub fn on_no_match(mut self, on_no_match: NoMatchMode) -> RegexpExtractBuilder
+    {
+        self.options.on_no_match = on_no_match;
+        self
+    }
+
+    pub fn on_invalid_pattern(mut self, on_invalid_pattern: ErrorMode) -> RegexpExtractBuilder
+    {
+        self.options.on_invalid_pattern = on_invalid_pattern;
+        self
+    }
+
+    /// Replaces every policy set so far with `options`.
+    pub fn options(mut self, options: RegexpExtractOptions) -> RegexpExtractBuilder
+    {
+        self.options = options;
+        self
+    }
+
+    /// Adds a step applied to every extracted value, after any added before it. NULL results
+    /// are left alone.
+    pub fn post_process(mut self, step: PostProcess) -> RegexpExtractBuilder
+    {
+        self.post_process.push(step);
+        self
+    }
+
+    /// How many distinct compiled patterns the UDF keeps, least recently used evicted first.
+    pub fn cache_capacity(mut self, cache_capacity: usize) -> RegexpExtractBuilder
+    {
+        self.cache_capacity = cache_capacity;
+        self
+    }
+
+    pub fn build(self) -> ScalarUDF
+    {
+        ScalarUDF::new_from_impl(RegexpExtract::from_builder(self))
+    }
+}
+
+
+
+pub fn register_regexp_extract_udf() -> ScalarUDF
+{
+    RegexpExtractBuilder::new().build()
+}
+
+pub fn register_regexp_extract_with_options(options: RegexpExtractOptions) -> ScalarUDF
+{
+    RegexpExtractBuilder::new().options(options).build()
+}
+
+/// `regexp_extract(input, pattern, group)` as an expression for the `DataFrame` API, using a
+/// default-configured UDF.
+pub fn regexp_extract_expr(input: Expr, pattern: &str, group: i64) -> Expr
+{
+    register_regexp_extract_udf().call(vec![input, lit(pattern), lit(group)])
 }
 
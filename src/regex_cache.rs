@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::engine::{Error, Regex};
+
+pub const DEFAULT_REGEX_CACHE_CAPACITY: usize = 256;
+
+/// Compiled regexes keyed by pattern, evicting the least recently used entry once `capacity`
+/// is reached. Shared by every batch a UDF instance evaluates, across execution threads.
+///
+/// One entry can also be pinned, normally the literal pattern seen while planning: it is never
+/// evicted, does not count towards `capacity`, and is read without taking the lock.
+#[derive(Debug)]
+pub struct RegexCache
+{
+    capacity: usize,
+    pinned: OnceLock<(String, Arc<Regex>)>,
+    inner: Mutex<RegexCacheInner>,
+    compilations: AtomicU64
+}
+
+#[derive(Debug, Default)]
+struct RegexCacheInner
+{
+    entries: HashMap<String, (Arc<Regex>, u64)>,
+    tick: u64
+}
+
+impl RegexCache
+{
+    /// A capacity of 0 disables caching: every lookup compiles.
+    pub fn new(capacity: usize) -> RegexCache
+    {
+        RegexCache{
+            capacity,
+            pinned: OnceLock::new(),
+            inner: Mutex::new(RegexCacheInner::default()),
+            compilations: AtomicU64::new(0),
+        }
+    }
+
+    pub fn capacity(&self) -> usize
+    {
+        self.capacity
+    }
+
+    /// How many times a pattern has been compiled through this cache.
+    pub fn compilations(&self) -> u64
+    {
+        self.compilations.load(Ordering::Relaxed)
+    }
+
+    /// Entries in the LRU part of the cache, not counting the pinned pattern.
+    pub fn len(&self) -> usize
+    {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.len() == 0
+    }
+
+    pub fn get_or_compile(&self, pattern: &str) -> Result<Arc<Regex>, Error>
+    {
+        self.get_or_insert_with(pattern, || Regex::new(pattern))
+    }
+
+    /// Like [`RegexCache::get_or_compile`] for regexes built with non-default options, which
+    /// `key` must distinguish from the same pattern built differently.
+    pub fn get_or_insert_with<F>(&self, key: &str, compile: F) -> Result<Arc<Regex>, Error>
+        where F: FnOnce() -> Result<Regex, Error>
+    {
+        if let Some((pinned, regex)) = self.pinned.get() && pinned == key
+        {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(pattern = key, "regex cache hit");
+            return Ok(regex.clone());
+        }
+
+        let compile = || {
+            self.compilations.fetch_add(1, Ordering::Relaxed);
+            compile()
+        };
+
+        if self.capacity == 0
+        {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(pattern = key, "regex cache miss");
+            return compile().map(Arc::new);
+        }
+
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.tick += 1;
+            let tick = inner.tick;
+            if let Some((regex, last_used)) = inner.entries.get_mut(key)
+            {
+                *last_used = tick;
+                #[cfg(feature = "tracing")]
+                tracing::debug!(pattern = key, "regex cache hit");
+                return Ok(regex.clone());
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(pattern = key, "regex cache miss");
+
+        // Compile without holding the lock so other threads are not stalled behind it.
+        let regex = Arc::new(compile()?);
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        if !inner.entries.contains_key(key) && inner.entries.len() >= self.capacity
+        {
+            let oldest = inner.entries.iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest
+            {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.entries.insert(key.to_string(), (regex.clone(), tick));
+        Ok(regex)
+    }
+
+    /// Compiles `key` into the pinned slot if it is still free. Once a different pattern is
+    /// pinned this behaves like [`RegexCache::get_or_insert_with`].
+    pub fn pin_with<F>(&self, key: &str, compile: F) -> Result<Arc<Regex>, Error>
+        where F: FnOnce() -> Result<Regex, Error>
+    {
+        if self.pinned.get().is_some()
+        {
+            return self.get_or_insert_with(key, compile);
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(pattern = key, "regex cache miss");
+        self.compilations.fetch_add(1, Ordering::Relaxed);
+        let regex = Arc::new(compile()?);
+        // Losing a race to pin is harmless: the regex just compiled is still valid for `key`.
+        let _ = self.pinned.set((key.to_string(), regex.clone()));
+        Ok(regex)
+    }
+}
+
+impl Default for RegexCache
+{
+    fn default() -> Self
+    {
+        Self::new(DEFAULT_REGEX_CACHE_CAPACITY)
+    }
+}
@@ -0,0 +1,160 @@
+use std::any::Any;
+use std::sync::Arc;
+use datafusion::arrow::array::{ArrayRef, ListBuilder, StringBuilder};
+use datafusion::arrow::datatypes::{DataType, Field};
+use datafusion::common::plan_err;
+use datafusion::error::Result;
+use datafusion::logical_expr::{ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, TypeSignature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use crate::engine::Regex;
+use crate::{RegexpExtractRequest, RegexpFlags, batch_rows, check_pattern};
+
+/// `regexp_extract_all(input, pattern, group [, flags])`: every match of `group`, as a `List(Utf8)`.
+/// The `o` flag makes matches overlap: `regexp_extract_all('aaa', '(aa)', 1, 'o')` is `[aa, aa]`.
+/// A group beyond the pattern's groups gives a NULL list.
+#[derive(Debug)]
+pub struct RegexpExtractAll
+{
+    signature: Signature
+}
+
+impl RegexpExtractAll
+{
+    pub fn new() -> RegexpExtractAll
+    {
+        RegexpExtractAll{
+            signature: Signature::one_of(vec![
+                TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
+                TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64, DataType::Utf8]),
+            ], Volatility::Immutable),
+        }
+    }
+}
+
+impl Default for RegexpExtractAll
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl ScalarUDFImpl for RegexpExtractAll
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_extract_all"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType>
+    {
+        match arg_types {
+            [DataType::Utf8, DataType::Utf8, DataType::Int64] |
+            [DataType::Utf8, DataType::Utf8, DataType::Int64, DataType::Utf8] =>
+                Ok(DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true)))),
+            _ =>
+                plan_err!("regexp_extract_all expects (Utf8, Utf8, Int64 [, Utf8]) got {:?}", arg_types)
+        }
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+
+        let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("regex", &args.args[1])?;
+        request.set("group", &args.args[2])?;
+        if let Some(flags) = args.args.get(3)
+        {
+            request.set("flags", flags)?;
+        }
+
+        Ok(ColumnarValue::Array(fulfill_all(&request)?))
+    }
+}
+
+fn fulfill_all(request: &RegexpExtractRequest) -> Result<ArrayRef>
+{
+    let mut builder = ListBuilder::new(StringBuilder::new());
+
+    if let (Some(input), Some(regex), Some(group)) = (request.input(), &request.regex, &request.group)
+    {
+        check_pattern(regex.as_str())?;
+        let flags = RegexpFlags::parse_all(request.flags.as_deref().unwrap_or_default())?;
+        let regexp = flags.compile(regex.as_str()).ok();
+        let group_count = regexp.as_ref().map_or(0, |regexp| regexp.captures_len());
+
+        for (row, s) in input.iter().enumerate()
+        {
+            if let (Some(regexp), Some(group_idx)) = (&regexp, group.at(row))
+            {
+                // Like `regexp_extract`, a group the pattern does not have is NULL rather than
+                // one "" per match.
+                if group_idx >= group_count
+                {
+                    builder.append(false);
+                    continue;
+                }
+                let s = s.unwrap_or_default();
+                let s = if flags.trim { s.trim() } else { s };
+                if flags.overlapping
+                {
+                    for m in overlapping_regex_extract(s, regexp, group_idx)
+                    {
+                        builder.values().append_value(m);
+                    }
+                }
+                else
+                {
+                    for m in all_regex_extract(s, regexp, group_idx)
+                    {
+                        builder.values().append_value(m);
+                    }
+                }
+            }
+            builder.append(true);
+        }
+    }
+
+    Ok(Arc::new(builder.finish()))
+}
+
+/// One entry per match; a group that did not take part in a match contributes `""`.
+fn all_regex_extract<'a>(input_str: &'a str, regex: &'a Regex, group: usize) -> impl Iterator<Item = &'a str>
+{
+    regex.captures_iter(input_str)
+        .map(move |cap| cap.get(group).map_or("", |m| m.as_str()))
+}
+
+/// Like [`all_regex_extract`], but each search resumes one character past the start of the
+/// previous match rather than at its end.
+fn overlapping_regex_extract<'a>(input_str: &'a str, regex: &'a Regex, group: usize) -> impl Iterator<Item = &'a str>
+{
+    let mut start = Some(0);
+    std::iter::from_fn(move || {
+        let cap = regex.captures_at(input_str, start?)?;
+        let whole = cap.get(0).unwrap();
+        // Step by the width of the character at the match start, never by one byte: an empty
+        // match can start right before a multibyte codepoint.
+        start = input_str[whole.start()..].chars().next().map(|c| whole.start() + c.len_utf8());
+        Some(cap.get(group).map_or("", |m| m.as_str()))
+    })
+}
+
+
+
+pub fn register_regexp_extract_all_udf() -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpExtractAll::new())
+}
@@ -0,0 +1,172 @@
+use std::any::Any;
+use std::sync::Arc;
+use std::sync::mpsc::{RecvTimeoutError, sync_channel};
+use std::thread;
+use std::time::Duration;
+use datafusion::arrow::array::{Array, ArrayRef, StringArray, StringBuilder};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{exec_err, internal_err, plan_err};
+use datafusion::error::Result;
+use datafusion::logical_expr::{ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use fancy_regex::{Regex, RegexBuilder};
+use crate::{GroupIndex, RegexpExtractError, RegexpExtractRequest, batch_rows, check_pattern};
+
+/// fancy-regex's own default, kept so a timed-out match still ends on its own.
+pub const DEFAULT_BACKTRACK_LIMIT: usize = 1_000_000;
+
+/// `regexp_extract_fancy(input, pattern, group)`: like `regexp_extract` with the `fancy-regex`
+/// backtracking engine, which adds lookaround and backreferences. Rows follow the
+/// `regexp_extract` defaults: `""` on no match, NULL for a group beyond the pattern's groups.
+///
+/// Backtracking can take exponential time, so each match can be given a `time_budget`: a match
+/// running past it fails the query instead of stalling it. With a budget, matching runs on a
+/// helper thread which cannot be interrupted mid-match and finishes in the background, bounded
+/// by `backtrack_limit` steps.
+#[derive(Debug)]
+pub struct RegexpExtractFancy
+{
+    signature: Signature,
+    time_budget: Option<Duration>,
+    backtrack_limit: usize
+}
+
+impl RegexpExtractFancy
+{
+    pub fn new(time_budget: Option<Duration>, backtrack_limit: usize) -> RegexpExtractFancy
+    {
+        RegexpExtractFancy{
+            signature: Signature::exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64], Volatility::Immutable),
+            time_budget,
+            backtrack_limit,
+        }
+    }
+}
+
+impl Default for RegexpExtractFancy
+{
+    fn default() -> Self
+    {
+        Self::new(None, DEFAULT_BACKTRACK_LIMIT)
+    }
+}
+
+impl ScalarUDFImpl for RegexpExtractFancy
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_extract_fancy"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType>
+    {
+        match arg_types {
+            [DataType::Utf8, DataType::Utf8, DataType::Int64] =>
+                Ok(DataType::Utf8),
+            _ =>
+                plan_err!("regexp_extract_fancy expects (Utf8, Utf8, Int64) got {:?}", arg_types)
+        }
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+
+        let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("regex", &args.args[1])?;
+        request.set("group", &args.args[2])?;
+
+        Ok(ColumnarValue::Array(self.fulfill(&request)?))
+    }
+}
+
+impl RegexpExtractFancy
+{
+    fn fulfill(&self, request: &RegexpExtractRequest) -> Result<ArrayRef>
+    {
+        let (Some(input), Some(pattern), Some(group)) = (request.input(), &request.regex, &request.group) else {
+            return exec_err!("regexp_extract_fancy called without input, pattern or group");
+        };
+        check_pattern(pattern)?;
+        let regexp = match RegexBuilder::new(pattern).backtrack_limit(self.backtrack_limit).build() {
+            Ok(regexp) => Arc::new(regexp),
+            Err(e) => return Err(RegexpExtractError::invalid_pattern(pattern, e).into())
+        };
+
+        let mut builder = StringBuilder::with_capacity(input.len(), 0);
+        match self.time_budget
+        {
+            None =>
+            {
+                for row in 0..input.len()
+                {
+                    builder.append_option(fancy_extract_row(&input, &regexp, group, row)?);
+                }
+            }
+            Some(budget) =>
+            {
+                // The helper runs ahead, so waiting on a row never takes longer than its match.
+                // Once we give up, its next send fails and it stops.
+                let (sender, receiver) = sync_channel(input.len());
+                let (input, regexp, group) = (input.into_owned(), regexp.clone(), group.clone());
+                let rows = input.len();
+                thread::spawn(move || {
+                    for row in 0..input.len()
+                    {
+                        if sender.send(fancy_extract_row(&input, &regexp, &group, row)).is_err()
+                        {
+                            break;
+                        }
+                    }
+                });
+                for _ in 0..rows
+                {
+                    match receiver.recv_timeout(budget) {
+                        Ok(value) => builder.append_option(value?),
+                        Err(RecvTimeoutError::Timeout) =>
+                            return Err(RegexpExtractError::MatchTimeout{ pattern: pattern.clone(), budget }.into()),
+                        Err(RecvTimeoutError::Disconnected) =>
+                            return internal_err!("regexp_extract_fancy matching thread stopped early")
+                    }
+                }
+            }
+        }
+        Ok(Arc::new(builder.finish()))
+    }
+}
+
+fn fancy_extract_row(input: &StringArray, regexp: &Regex, group: &GroupIndex, row: usize) -> Result<Option<String>>
+{
+    let Some(group_idx) = group.at(row) else {
+        return Ok(Some(String::new()));
+    };
+    if group_idx >= regexp.captures_len()
+    {
+        return Ok(None);
+    }
+    let s = if input.is_null(row) { "" } else { input.value(row) };
+    match regexp.captures(s) {
+        Ok(cap) =>
+            Ok(Some(cap.and_then(|cap| cap.get(group_idx)).map_or("", |m| m.as_str()).to_string())),
+        Err(e) =>
+            Err(RegexpExtractError::MatchFailed{ pattern: regexp.as_str().to_string(), source: Box::new(e) }.into())
+    }
+}
+
+
+
+pub fn register_regexp_extract_fancy_udf(time_budget: Option<Duration>) -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpExtractFancy::new(time_budget, DEFAULT_BACKTRACK_LIMIT))
+}
@@ -0,0 +1,207 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+use datafusion::arrow::array::{Array, ArrayRef, StringArray, StringBuilder};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{ScalarValue, exec_err, plan_err};
+use datafusion::error::Result;
+use datafusion::logical_expr::{ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use crate::engine::{Captures, Regex};
+use crate::{RegexpExtractError, RegexpExtractRequest, batch_rows, check_pattern};
+
+/// `regexp_extract_first_of(input, patterns, group)`: `group` of the first match of the first
+/// pattern in `patterns`, a literal `List` or `FixedSizeList` of Utf8, that matches the row.
+/// Rows matching none of them are `""`, as with `regexp_extract`; a group beyond the winning
+/// pattern's groups is NULL.
+#[derive(Debug)]
+pub struct RegexpExtractFirstOf
+{
+    signature: Signature,
+    /// The last pattern list compiled, reused while batches keep passing the same one.
+    compiled: Mutex<Option<Arc<PatternList>>>
+}
+
+impl RegexpExtractFirstOf
+{
+    pub fn new() -> RegexpExtractFirstOf
+    {
+        RegexpExtractFirstOf{
+            signature: Signature::user_defined(Volatility::Immutable),
+            compiled: Mutex::new(None),
+        }
+    }
+
+    fn patterns(&self, patterns: Vec<String>) -> Result<Arc<PatternList>>
+    {
+        let mut compiled = self.compiled.lock().unwrap();
+        if let Some(list) = compiled.as_ref() && list.patterns == patterns
+        {
+            return Ok(list.clone());
+        }
+        let list = Arc::new(PatternList::compile(patterns)?);
+        *compiled = Some(list.clone());
+        Ok(list)
+    }
+}
+
+impl Default for RegexpExtractFirstOf
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+fn is_utf8_list(data_type: &DataType) -> bool
+{
+    match data_type {
+        DataType::List(field) | DataType::FixedSizeList(field, _) => field.data_type() == &DataType::Utf8,
+        _ => false
+    }
+}
+
+impl ScalarUDFImpl for RegexpExtractFirstOf
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_extract_first_of"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>>
+    {
+        match arg_types {
+            [DataType::Utf8, patterns, group] if is_utf8_list(patterns) && group.is_integer() =>
+                Ok(vec![DataType::Utf8, patterns.clone(), DataType::Int64]),
+            _ =>
+                plan_err!("regexp_extract_first_of expects (Utf8, List(Utf8), Int64) got {:?}", arg_types)
+        }
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType>
+    {
+        match arg_types {
+            [DataType::Utf8, patterns, DataType::Int64] if is_utf8_list(patterns) =>
+                Ok(DataType::Utf8),
+            _ =>
+                plan_err!("regexp_extract_first_of expects (Utf8, List(Utf8), Int64) got {:?}", arg_types)
+        }
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+
+        let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("group", &args.args[2])?;
+
+        let values = match &args.args[1] {
+            ColumnarValue::Scalar(ScalarValue::List(list)) if list.len() == 1 => list.value(0),
+            ColumnarValue::Scalar(ScalarValue::FixedSizeList(list)) if list.len() == 1 => list.value(0),
+            other => return Err(RegexpExtractError::WrongArgType{
+                field: "patterns".to_string(),
+                got: other.data_type().to_string(),
+            }.into())
+        };
+        let patterns = self.patterns(pattern_strings(&values)?)?;
+
+        Ok(ColumnarValue::Array(fulfill_first_of(&request, &patterns)?))
+    }
+}
+
+fn pattern_strings(values: &ArrayRef) -> Result<Vec<String>>
+{
+    let Some(values) = values.as_any().downcast_ref::<StringArray>() else {
+        return Err(RegexpExtractError::WrongArgType{
+            field: "patterns".to_string(),
+            got: values.data_type().to_string(),
+        }.into());
+    };
+    values.iter()
+        .map(|pattern| match pattern {
+            Some(pattern) => check_pattern(pattern).map(|_| pattern.to_string()),
+            None => Err(RegexpExtractError::WrongArgType{ field: "patterns".to_string(), got: "NULL".to_string() }.into())
+        })
+        .collect()
+}
+
+/// Every pattern compiled once, plus a `RegexSet` over all of them so a row is scanned once to
+/// learn which pattern matches first. `regex-lite` has no `RegexSet`: there each pattern is
+/// tried in turn.
+#[derive(Debug)]
+struct PatternList
+{
+    patterns: Vec<String>,
+    regexes: Vec<Regex>,
+    #[cfg(not(feature = "regex-lite"))]
+    set: regex::RegexSet
+}
+
+impl PatternList
+{
+    fn compile(patterns: Vec<String>) -> Result<PatternList>
+    {
+        let regexes = patterns.iter()
+            .map(|pattern| Regex::new(pattern).map_err(|source| RegexpExtractError::invalid_pattern(pattern, source)))
+            .collect::<std::result::Result<Vec<Regex>, RegexpExtractError>>()?;
+        #[cfg(not(feature = "regex-lite"))]
+        // Every pattern compiled on its own above; the set can still exceed the size limit.
+        let set = regex::RegexSet::new(&patterns)
+            .map_err(|source| RegexpExtractError::invalid_pattern(&format!("{:?}", patterns), source))?;
+        Ok(PatternList{
+            patterns,
+            regexes,
+            #[cfg(not(feature = "regex-lite"))]
+            set,
+        })
+    }
+
+    #[cfg(not(feature = "regex-lite"))]
+    fn first_captures<'s>(&self, s: &'s str) -> Option<Captures<'s>>
+    {
+        let first = self.set.matches(s).iter().next()?;
+        self.regexes[first].captures(s)
+    }
+
+    #[cfg(feature = "regex-lite")]
+    fn first_captures<'s>(&self, s: &'s str) -> Option<Captures<'s>>
+    {
+        self.regexes.iter().find_map(|regex| regex.captures(s))
+    }
+}
+
+fn fulfill_first_of(request: &RegexpExtractRequest, patterns: &PatternList) -> Result<ArrayRef>
+{
+    let (Some(input), Some(group)) = (request.input(), &request.group) else {
+        return exec_err!("regexp_extract_first_of called without input or group");
+    };
+
+    let mut builder = StringBuilder::with_capacity(input.len(), 0);
+    for (row, s) in input.iter().enumerate()
+    {
+        let value = match (patterns.first_captures(s.unwrap_or_default()), group.at(row)) {
+            (Some(cap), Some(group_idx)) if group_idx >= cap.len() => None,
+            (Some(cap), Some(group_idx)) => Some(cap.get(group_idx).map_or("", |m| m.as_str())),
+            _ => Some("")
+        };
+        builder.append_option(value);
+    }
+    Ok(Arc::new(builder.finish()))
+}
+
+
+
+pub fn register_regexp_extract_first_of_udf() -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpExtractFirstOf::new())
+}
@@ -0,0 +1,136 @@
+use std::any::Any;
+use std::sync::Arc;
+use datafusion::arrow::array::{Array, ArrayRef, FixedSizeListBuilder, StringBuilder};
+use datafusion::arrow::datatypes::{DataType, Field, FieldRef};
+use datafusion::common::{ScalarValue, exec_err, internal_err, plan_err};
+use datafusion::error::Result;
+use datafusion::logical_expr::{ReturnFieldArgs, ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use crate::engine::Regex;
+use crate::{RegexpExtractError, RegexpExtractRequest, batch_rows, check_pattern};
+
+/// `regexp_extract_fixed(input, pattern)`: every capturing group of the first match, as a
+/// `FixedSizeList(Utf8, N)` with one slot per group. The pattern must be a literal, since `N`
+/// is derived from it while planning. A row with no match has `N` NULL slots, a group that
+/// did not take part in the match is `""`, and NULL input yields a NULL list.
+#[derive(Debug)]
+pub struct RegexpExtractFixed
+{
+    signature: Signature
+}
+
+impl RegexpExtractFixed
+{
+    pub fn new() -> RegexpExtractFixed
+    {
+        RegexpExtractFixed{
+            signature: Signature::exact(vec![DataType::Utf8, DataType::Utf8], Volatility::Immutable),
+        }
+    }
+}
+
+impl Default for RegexpExtractFixed
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl ScalarUDFImpl for RegexpExtractFixed
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_extract_fixed"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType>
+    {
+        internal_err!("regexp_extract_fixed derives its return type from the pattern, use return_field_from_args")
+    }
+
+    fn return_field_from_args(&self, args: ReturnFieldArgs) -> Result<FieldRef>
+    {
+        let width = match args.scalar_arguments.get(1) {
+            Some(Some(ScalarValue::Utf8(Some(pattern)))) =>
+                group_width(pattern)?,
+            _ =>
+                return plan_err!("regexp_extract_fixed requires a literal Utf8 pattern")
+        };
+        let item = Arc::new(Field::new_list_field(DataType::Utf8, true));
+        Ok(Arc::new(Field::new(self.name(), DataType::FixedSizeList(item, width), true)))
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+
+        let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("regex", &args.args[1])?;
+
+        let DataType::FixedSizeList(_, width) = args.return_type() else {
+            return internal_err!("regexp_extract_fixed planned with a non-fixed-size-list return type");
+        };
+
+        Ok(ColumnarValue::Array(fulfill_fixed(&request, *width)?))
+    }
+}
+
+/// The number of capturing groups in `pattern`, which must have at least one.
+fn group_width(pattern: &str) -> Result<i32>
+{
+    check_pattern(pattern)?;
+    match Regex::new(pattern) {
+        Ok(regexp) if regexp.captures_len() > 1 =>
+            Ok(i32::try_from(regexp.captures_len() - 1).unwrap_or(i32::MAX)),
+        Ok(_) =>
+            plan_err!("regexp_extract_fixed requires a pattern with at least one capturing group"),
+        Err(e) =>
+            plan_err!("Invalid pattern for regexp_extract_fixed: {}", e)
+    }
+}
+
+fn fulfill_fixed(request: &RegexpExtractRequest, width: i32) -> Result<ArrayRef>
+{
+    let (Some(input), Some(regex)) = (request.input(), &request.regex) else {
+        return exec_err!("regexp_extract_fixed called without input or pattern");
+    };
+    let regexp = match Regex::new(regex.as_str()) {
+        Ok(regexp) => regexp,
+        Err(source) => return Err(RegexpExtractError::invalid_pattern(regex, source).into())
+    };
+
+    let mut builder = FixedSizeListBuilder::with_capacity(StringBuilder::new(), width, input.len());
+    for s in input.iter()
+    {
+        let cap = s.and_then(|s| regexp.captures(s));
+        for group in 1..=width as usize
+        {
+            match &cap {
+                Some(cap) => builder.values().append_value(cap.get(group).map_or("", |m| m.as_str())),
+                None => builder.values().append_null()
+            }
+        }
+        builder.append(s.is_some());
+    }
+
+    Ok(Arc::new(builder.finish()))
+}
+
+
+
+pub fn register_regexp_extract_fixed_udf() -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpExtractFixed::new())
+}
@@ -0,0 +1,143 @@
+use std::any::Any;
+use std::sync::Arc;
+use datafusion::arrow::array::{ArrayRef, StringBuilder, StructArray};
+use datafusion::arrow::datatypes::{DataType, Field, FieldRef, Fields};
+use datafusion::common::{ScalarValue, exec_err, internal_err, plan_err};
+use datafusion::error::Result;
+use datafusion::logical_expr::{ReturnFieldArgs, ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use crate::engine::Regex;
+use crate::{RegexpExtractError, RegexpExtractRequest, batch_rows, check_pattern};
+
+/// `regexp_extract_named(input, pattern)`: the named groups of the first match, as a `Struct`
+/// with one `Utf8` field per `(?<name>...)` group. The pattern must be a literal, since the
+/// struct's fields are derived from it while planning.
+#[derive(Debug)]
+pub struct RegexpExtractNamed
+{
+    signature: Signature
+}
+
+impl RegexpExtractNamed
+{
+    pub fn new() -> RegexpExtractNamed
+    {
+        RegexpExtractNamed{
+            signature: Signature::exact(vec![DataType::Utf8, DataType::Utf8], Volatility::Immutable),
+        }
+    }
+}
+
+impl Default for RegexpExtractNamed
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl ScalarUDFImpl for RegexpExtractNamed
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_extract_named"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType>
+    {
+        internal_err!("regexp_extract_named derives its return type from the pattern, use return_field_from_args")
+    }
+
+    fn return_field_from_args(&self, args: ReturnFieldArgs) -> Result<FieldRef>
+    {
+        let fields = match args.scalar_arguments.get(1) {
+            Some(Some(ScalarValue::Utf8(Some(pattern)))) =>
+                named_group_fields(pattern)?,
+            _ =>
+                return plan_err!("regexp_extract_named requires a literal Utf8 pattern")
+        };
+        Ok(Arc::new(Field::new(self.name(), DataType::Struct(fields), true)))
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+
+        let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("regex", &args.args[1])?;
+
+        let DataType::Struct(fields) = args.return_type() else {
+            return internal_err!("regexp_extract_named planned with a non-struct return type");
+        };
+
+        Ok(ColumnarValue::Array(fulfill_named(&request, fields)?))
+    }
+}
+
+/// A struct needs at least one field, so the pattern must have a named group.
+fn named_group_fields(pattern: &str) -> Result<Fields>
+{
+    check_pattern(pattern)?;
+    match Regex::new(pattern)
+    {
+        Ok(regexp) =>
+        {
+            let fields = regexp.capture_names()
+                .flatten()
+                .map(|name| Field::new(name, DataType::Utf8, true))
+                .collect::<Fields>();
+            if fields.is_empty()
+            {
+                return plan_err!("regexp_extract_named pattern '{}' has no named groups", pattern);
+            }
+            Ok(fields)
+        }
+        Err(e) =>
+            plan_err!("Invalid pattern for regexp_extract_named: {}", e)
+    }
+}
+
+fn fulfill_named(request: &RegexpExtractRequest, fields: &Fields) -> Result<ArrayRef>
+{
+    let (Some(input), Some(regex)) = (request.input(), &request.regex) else {
+        return exec_err!("regexp_extract_named called without input or pattern");
+    };
+    let regexp = match Regex::new(regex.as_str()) {
+        Ok(regexp) => regexp,
+        Err(source) => return Err(RegexpExtractError::invalid_pattern(regex, source).into())
+    };
+
+    let mut builders = fields.iter().map(|_| StringBuilder::new()).collect::<Vec<StringBuilder>>();
+    for s in input.iter()
+    {
+        let cap = regexp.captures(s.unwrap_or_default());
+        for (field, builder) in fields.iter().zip(builders.iter_mut())
+        {
+            builder.append_value(cap.as_ref().and_then(|c| c.name(field.name())).map_or("", |m| m.as_str()));
+        }
+    }
+
+    let columns = builders.iter_mut()
+        .map(|builder| Arc::new(builder.finish()) as ArrayRef)
+        .collect::<Vec<ArrayRef>>();
+
+    Ok(Arc::new(StructArray::try_new(fields.clone(), columns, None)?))
+}
+
+
+
+pub fn register_regexp_extract_named_udf() -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpExtractNamed::new())
+}
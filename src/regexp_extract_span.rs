@@ -0,0 +1,148 @@
+use std::any::Any;
+use std::sync::Arc;
+use datafusion::arrow::array::{ArrayRef, Int64Builder, StringBuilder, StructArray};
+use datafusion::arrow::datatypes::{DataType, Field, Fields};
+use datafusion::common::plan_err;
+use datafusion::error::Result;
+use datafusion::logical_expr::{ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use crate::engine::Regex;
+use crate::{RegexpExtractRequest, batch_rows, char_position, check_pattern};
+
+/// `regexp_extract_span(input, pattern, group)`: `group` of the first match together with its
+/// span, as `Struct{value: Utf8, start: Int64, end: Int64}`. `start` and `end` are 1-based
+/// character positions with the same meaning as `regexp_instr` and `regexp_instr_end`. Rows with
+/// no match, a NULL input or an out-of-range group have all three fields NULL.
+#[derive(Debug)]
+pub struct RegexpExtractSpan
+{
+    signature: Signature
+}
+
+impl RegexpExtractSpan
+{
+    pub fn new() -> RegexpExtractSpan
+    {
+        RegexpExtractSpan{
+            signature: Signature::exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64], Volatility::Immutable),
+        }
+    }
+}
+
+impl Default for RegexpExtractSpan
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+fn span_fields() -> Fields
+{
+    Fields::from(vec![
+        Field::new("value", DataType::Utf8, true),
+        Field::new("start", DataType::Int64, true),
+        Field::new("end", DataType::Int64, true),
+    ])
+}
+
+impl ScalarUDFImpl for RegexpExtractSpan
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_extract_span"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType>
+    {
+        match arg_types {
+            [DataType::Utf8, DataType::Utf8, DataType::Int64] =>
+                Ok(DataType::Struct(span_fields())),
+            _ =>
+                plan_err!("regexp_extract_span expects (Utf8, Utf8, Int64) got {:?}", arg_types)
+        }
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+
+        let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("regex", &args.args[1])?;
+        request.set("group", &args.args[2])?;
+
+        Ok(ColumnarValue::Array(fulfill_span(&request)?))
+    }
+}
+
+fn fulfill_span(request: &RegexpExtractRequest) -> Result<ArrayRef>
+{
+    let mut values = StringBuilder::new();
+    let mut starts = Int64Builder::new();
+    let mut ends = Int64Builder::new();
+
+    if let (Some(input), Some(regex), Some(group)) = (request.input(), &request.regex, &request.group)
+    {
+        check_pattern(regex.as_str())?;
+        let regexp = Regex::new(regex.as_str()).ok();
+
+        // One capture per row feeds all three fields.
+        for (row, s) in input.iter().enumerate()
+        {
+            let span = match (s, &regexp, group.at(row)) {
+                (Some(s), Some(regexp), Some(group_idx)) => single_regex_span(s, regexp, group_idx),
+                _ => None
+            };
+            match span
+            {
+                Some((value, start, end)) =>
+                {
+                    values.append_value(value);
+                    starts.append_value(start);
+                    ends.append_value(end);
+                }
+                None =>
+                {
+                    values.append_null();
+                    starts.append_null();
+                    ends.append_null();
+                }
+            }
+        }
+    }
+
+    let columns = vec![
+        Arc::new(values.finish()) as ArrayRef,
+        Arc::new(starts.finish()) as ArrayRef,
+        Arc::new(ends.finish()) as ArrayRef,
+    ];
+    Ok(Arc::new(StructArray::try_new(span_fields(), columns, None)?))
+}
+
+fn single_regex_span<'a>(input_str: &'a str, regex: &Regex, group: usize) -> Option<(&'a str, i64, i64)>
+{
+    regex.captures(input_str)
+        .and_then(|cap| cap.get(group))
+        .map(|m| {
+            let start = char_position(input_str, m.start());
+            (m.as_str(), start, start + m.as_str().chars().count() as i64)
+        })
+}
+
+
+
+pub fn register_regexp_extract_span_udf() -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpExtractSpan::new())
+}
@@ -0,0 +1,121 @@
+use std::any::Any;
+use std::sync::Arc;
+use datafusion::arrow::array::{ArrayRef, StringArray};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::{ScalarValue, plan_err};
+use datafusion::error::Result;
+use datafusion::logical_expr::{ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use crate::engine::Regex;
+use crate::{RegexpExtractError, RegexpExtractRequest, batch_rows, check_pattern};
+
+/// `regexp_extract_template(input, pattern, template)`: `template` expanded against the first
+/// match, where `$1`, `${name}` and so on refer to its groups and `$$` is a literal `$`. Unlike
+/// `regexp_replace` only the expansion is returned, not the rest of the input. As with
+/// `regexp_extract`, a row that does not match yields `""` and NULL input is treated as `""`.
+#[derive(Debug)]
+pub struct RegexpExtractTemplate
+{
+    signature: Signature
+}
+
+impl RegexpExtractTemplate
+{
+    pub fn new() -> RegexpExtractTemplate
+    {
+        RegexpExtractTemplate{
+            signature: Signature::exact(vec![DataType::Utf8, DataType::Utf8, DataType::Utf8], Volatility::Immutable),
+        }
+    }
+}
+
+impl Default for RegexpExtractTemplate
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl ScalarUDFImpl for RegexpExtractTemplate
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_extract_template"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType>
+    {
+        match arg_types {
+            [DataType::Utf8, DataType::Utf8, DataType::Utf8] =>
+                Ok(DataType::Utf8),
+            _ =>
+                plan_err!("regexp_extract_template expects (Utf8, Utf8, Utf8) got {:?}", arg_types)
+        }
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+
+        let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("regex", &args.args[1])?;
+        let ColumnarValue::Scalar(ScalarValue::Utf8(Some(template))) = &args.args[2] else {
+            return Err(RegexpExtractError::WrongArgType{ field: "template".to_string(), got: args.args[2].data_type().to_string() }.into());
+        };
+
+        Ok(ColumnarValue::Array(fulfill_template(&request, template)?))
+    }
+}
+
+fn fulfill_template(request: &RegexpExtractRequest, template: &str) -> Result<ArrayRef>
+{
+    let rows = request.input.as_ref().map_or(0, |input| input.len());
+    let empty = || Arc::new(StringArray::from(vec![""; rows])) as ArrayRef;
+
+    if let (Some(input), Some(regex)) = (request.input(), &request.regex)
+    {
+        check_pattern(regex.as_str())?;
+        let Ok(regexp) = Regex::new(regex.as_str()) else {
+            return Ok(empty());
+        };
+
+        let out: StringArray = input.iter()
+            .map(|s| Some(single_regex_template(s.unwrap_or_default(), &regexp, template)))
+            .collect();
+        Ok(Arc::new(out))
+    }
+    else
+    {
+        Ok(empty())
+    }
+}
+
+fn single_regex_template(input_str: &str, regex: &Regex, template: &str) -> String
+{
+    regex.captures(input_str)
+        .map(|cap| {
+            let mut dst = String::new();
+            cap.expand(template, &mut dst);
+            dst
+        })
+        .unwrap_or_default()
+}
+
+
+
+pub fn register_regexp_extract_template_udf() -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpExtractTemplate::new())
+}
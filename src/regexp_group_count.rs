@@ -0,0 +1,94 @@
+use std::any::Any;
+use std::sync::Arc;
+use datafusion::arrow::array::{Array, Int64Array, StringArray};
+use datafusion::arrow::datatypes::{DataType};
+use datafusion::common::plan_err;
+use datafusion::error::Result;
+use datafusion::logical_expr::{ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use crate::{RegexpExtractError, batch_rows, capture_count, check_pattern};
+
+/// `regexp_group_count(pattern)`: how many capturing groups `pattern` has, not counting the
+/// whole match, i.e. the largest group `regexp_extract` accepts. A NULL pattern yields NULL;
+/// an invalid one is an error.
+#[derive(Debug)]
+pub struct RegexpGroupCount
+{
+    signature: Signature
+}
+
+impl RegexpGroupCount
+{
+    pub fn new() -> RegexpGroupCount
+    {
+        RegexpGroupCount{
+            signature: Signature::exact(vec![DataType::Utf8], Volatility::Immutable),
+        }
+    }
+}
+
+impl Default for RegexpGroupCount
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl ScalarUDFImpl for RegexpGroupCount
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_group_count"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType>
+    {
+        match arg_types {
+            [DataType::Utf8] =>
+                Ok(DataType::Int64),
+            _ =>
+                plan_err!("regexp_group_count expects (Utf8) got {:?}", arg_types)
+        }
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+        let patterns = args.args[0].to_array(rows)?;
+        let Some(patterns) = patterns.as_any().downcast_ref::<StringArray>() else {
+            return Err(RegexpExtractError::WrongArgType{
+                field: "pattern".to_string(),
+                got: patterns.data_type().to_string(),
+            }.into());
+        };
+
+        let out = patterns.iter()
+            .map(|pattern| pattern.map(group_count).transpose())
+            .collect::<Result<Int64Array>>()?;
+        Ok(ColumnarValue::Array(Arc::new(out)))
+    }
+}
+
+fn group_count(pattern: &str) -> Result<i64>
+{
+    check_pattern(pattern)?;
+    Ok(capture_count(pattern)? as i64 - 1)
+}
+
+
+
+pub fn register_regexp_group_count_udf() -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpGroupCount::new())
+}
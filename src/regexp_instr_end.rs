@@ -0,0 +1,117 @@
+use std::any::Any;
+use std::sync::Arc;
+use datafusion::arrow::array::{ArrayRef, Int64Array};
+use datafusion::arrow::datatypes::{DataType};
+use datafusion::common::plan_err;
+use datafusion::error::Result;
+use datafusion::logical_expr::{ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use crate::engine::Regex;
+use crate::{RegexpExtractRequest, batch_rows, char_position, check_pattern};
+
+/// `regexp_instr_end(input, pattern, group)`: the 1-based character position just after `group`
+/// in the first match, the counterpart of the start position reported by `regexp_instr`.
+/// NULL input yields NULL; no match or an out-of-range group yields 0.
+#[derive(Debug)]
+pub struct RegexpInstrEnd
+{
+    signature: Signature
+}
+
+impl RegexpInstrEnd
+{
+    pub fn new() -> RegexpInstrEnd
+    {
+        RegexpInstrEnd{
+            signature: Signature::exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64], Volatility::Immutable),
+        }
+    }
+}
+
+impl Default for RegexpInstrEnd
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl ScalarUDFImpl for RegexpInstrEnd
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_instr_end"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType>
+    {
+        match arg_types {
+            [DataType::Utf8, DataType::Utf8, DataType::Int64] =>
+                Ok(DataType::Int64),
+            _ =>
+                plan_err!("regexp_instr_end expects (Utf8, Utf8, Int64) got {:?}", arg_types)
+        }
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+
+        let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("regex", &args.args[1])?;
+        request.set("group", &args.args[2])?;
+
+        Ok(ColumnarValue::Array(fulfill_instr_end(&request)?))
+    }
+}
+
+fn fulfill_instr_end(request: &RegexpExtractRequest) -> Result<ArrayRef>
+{
+    let empty = Arc::new(Int64Array::from(Vec::<i64>::new())) as ArrayRef;
+
+    if let (Some(input), Some(regex), Some(group)) = (request.input(), &request.regex, &request.group)
+    {
+        check_pattern(regex.as_str())?;
+        let regexp = Regex::new(regex.as_str()).ok();
+
+        let out: Int64Array = input.iter().enumerate()
+            .map(|(row, s)| s.map(|s| match (&regexp, group.at(row)) {
+                (Some(regexp), Some(group_idx)) => single_regex_instr_end(s, regexp, group_idx),
+                _ => 0
+            }))
+            .collect();
+        Ok(Arc::new(out))
+    }
+    else
+    {
+        Ok(empty)
+    }
+}
+
+/// Regex offsets are bytes; count the characters before `m.end()` so multibyte input reports
+/// character positions.
+fn single_regex_instr_end(input_str: &str, regex: &Regex, group: usize) -> i64
+{
+    regex.captures(input_str)
+        .and_then(|cap| cap.get(group))
+        .map(|m| char_position(input_str, m.end()))
+        .unwrap_or(0)
+}
+
+
+
+pub fn register_regexp_instr_end_udf() -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpInstrEnd::new())
+}
@@ -0,0 +1,112 @@
+use std::any::Any;
+use std::sync::Arc;
+use datafusion::arrow::array::{ArrayRef, ListBuilder, StringBuilder};
+use datafusion::arrow::datatypes::{DataType, Field};
+use datafusion::common::plan_err;
+use datafusion::error::Result;
+use datafusion::logical_expr::{ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::physical_plan::ColumnarValue;
+use crate::engine::Regex;
+use crate::{RegexpExtractRequest, batch_rows, check_pattern};
+
+/// `regexp_matches(input, pattern)`: every non-overlapping match of the whole pattern, as a
+/// `List(Utf8)`, like Postgres' `regexp_matches` with the `g` flag. NULL input yields a NULL
+/// list; no match an empty one.
+#[derive(Debug)]
+pub struct RegexpMatches
+{
+    signature: Signature
+}
+
+impl RegexpMatches
+{
+    pub fn new() -> RegexpMatches
+    {
+        RegexpMatches{
+            signature: Signature::exact(vec![DataType::Utf8, DataType::Utf8], Volatility::Immutable),
+        }
+    }
+}
+
+impl Default for RegexpMatches
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl ScalarUDFImpl for RegexpMatches
+{
+    fn as_any(&self) -> &dyn Any
+    {
+        self
+    }
+
+    fn name(&self) -> &str
+    {
+        "regexp_matches"
+    }
+
+    fn signature(&self) -> &Signature
+    {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType>
+    {
+        match arg_types {
+            [DataType::Utf8, DataType::Utf8] =>
+                Ok(DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true)))),
+            _ =>
+                plan_err!("regexp_matches expects (Utf8, Utf8) got {:?}", arg_types)
+        }
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue>
+    {
+        let rows = batch_rows(&args.args, args.number_rows)?;
+
+        let mut request: RegexpExtractRequest = RegexpExtractRequest::new();
+        request.set("input", &ColumnarValue::Array(args.args[0].to_array(rows)?))?;
+        request.set("regex", &args.args[1])?;
+
+        Ok(ColumnarValue::Array(fulfill_matches(&request)?))
+    }
+}
+
+fn fulfill_matches(request: &RegexpExtractRequest) -> Result<ArrayRef>
+{
+    let mut builder = ListBuilder::new(StringBuilder::new());
+
+    if let (Some(input), Some(regex)) = (request.input(), &request.regex)
+    {
+        check_pattern(regex.as_str())?;
+        let regexp = Regex::new(regex.as_str()).ok();
+
+        for s in input.iter()
+        {
+            match (s, &regexp) {
+                (Some(s), Some(regexp)) =>
+                {
+                    for m in regexp.find_iter(s)
+                    {
+                        builder.values().append_value(m.as_str());
+                    }
+                    builder.append(true);
+                }
+                (Some(_), None) => builder.append(true),
+                (None, _) => builder.append(false)
+            }
+        }
+    }
+
+    Ok(Arc::new(builder.finish()))
+}
+
+
+
+pub fn register_regexp_matches_udf() -> ScalarUDF
+{
+    ScalarUDF::new_from_impl(RegexpMatches::new())
+}
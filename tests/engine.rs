@@ -0,0 +1,37 @@
+//! Behaviour that must hold whichever regex engine the crate is built with:
+//! `cargo test` (regex) and `cargo test --no-default-features --features regex-lite`.
+use datafusion::arrow::array::{Array, StringArray};
+use datafusion::error::Result;
+use regexp_extract::{RegexCache, engine, extract_all, regexp_extract_array};
+
+#[test]
+fn test_engine_extract_groups() -> Result<()> {
+    let input = StringArray::from(vec![Some("abc123"), Some("xyz"), None]);
+    let out = regexp_extract_array(&input, r"([a-z]+)(\d+)", 2)?;
+    assert_eq!(out.iter().collect::<Vec<_>>(), vec![Some("123"), Some(""), Some("")]);
+
+    let out = regexp_extract_array(&input, r"([a-z]+)(\d+)", 3)?;
+    assert!(out.is_null(0), "group out of range");
+    Ok(())
+}
+
+#[test]
+fn test_engine_invalid_and_empty_pattern() {
+    let input = StringArray::from(vec!["abc"]);
+    assert!(regexp_extract_array(&input, r"(", 1).is_err());
+    assert!(regexp_extract_array(&input, "", 0).is_err());
+    assert!(extract_all(&[Some("abc")], r"(", 1).is_err());
+}
+
+#[test]
+fn test_engine_extract_all_plain_values() {
+    let out = extract_all(&[Some("k=v"), Some("none")], r"(\w+)=(\w+)", 1).unwrap();
+    assert_eq!(out, vec![Some("k".to_string()), Some(String::new())]);
+}
+
+#[test]
+fn test_engine_cache_compiles_with_selected_engine() {
+    let cache = RegexCache::new(2);
+    let regex: std::sync::Arc<engine::Regex> = cache.get_or_compile(r"(\d+)").unwrap();
+    assert_eq!(regex.captures_len(), 2);
+}
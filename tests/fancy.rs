@@ -0,0 +1,68 @@
+//! `regexp_extract_fancy`, built only with `cargo test --features fancy`.
+#![cfg(feature = "fancy")]
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use datafusion::arrow::array::{ArrayRef, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::MemTable;
+use datafusion::error::Result;
+use datafusion::logical_expr::ScalarUDF;
+use datafusion::prelude::*;
+use regexp_extract::{RegexpExtractError, RegexpExtractFancy, register_regexp_extract_fancy_udf};
+
+async fn run_fancy(udf: ScalarUDF, rows: &[&str], sql: &str) -> Result<Vec<Option<String>>> {
+    let ctx = SessionContext::new();
+    ctx.register_udf(udf);
+    let schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, true)]));
+    let col: ArrayRef = Arc::new(StringArray::from(rows.to_vec()));
+    let batch = RecordBatch::try_new(schema.clone(), vec![col])?;
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let out = batches[0].column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    Ok(out.iter().map(|v| v.map(str::to_string)).collect())
+}
+
+#[tokio::test]
+async fn test_fancy_lookaround_and_backreference() -> Result<()> {
+    let rows = ["price: 42 USD", "hello hello world", "none"];
+    let udf = register_regexp_extract_fancy_udf(Some(Duration::from_secs(5)));
+    let out = run_fancy(udf.clone(), &rows, r"SELECT regexp_extract_fancy(s, '(\d+)(?= USD)', 1) FROM t").await?;
+    assert_eq!(out, vec![Some("42".to_string()), Some(String::new()), Some(String::new())]);
+
+    let out = run_fancy(udf, &rows, r"SELECT regexp_extract_fancy(s, '\b(\w+) \1\b', 1) FROM t").await?;
+    assert_eq!(out, vec![Some(String::new()), Some("hello".to_string()), Some(String::new())]);
+    Ok(())
+}
+
+/// Exponential backtracking hits the time budget instead of hanging the query
+#[tokio::test]
+async fn test_fancy_time_budget() -> Result<()> {
+    let input = format!("{}b", "a".repeat(40));
+    let udf = ScalarUDF::new_from_impl(RegexpExtractFancy::new(Some(Duration::from_millis(50)), 50_000_000));
+
+    let started = Instant::now();
+    let err = run_fancy(udf, &[input.as_str()], r"SELECT regexp_extract_fancy(s, '^(a+)+(?=c)', 1) FROM t")
+        .await
+        .unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(5), "took {:?}", started.elapsed());
+    assert!(matches!(RegexpExtractError::find(&err), Some(RegexpExtractError::MatchTimeout { .. })), "{err:?}");
+    Ok(())
+}
+
+/// Without a time budget, running out of backtracking steps is a typed error, and an empty
+/// pattern is refused as by `regexp_extract`
+#[tokio::test]
+async fn test_fancy_match_errors() -> Result<()> {
+    let input = format!("{}b", "a".repeat(40));
+    let udf = ScalarUDF::new_from_impl(RegexpExtractFancy::new(None, 1_000));
+    let err = run_fancy(udf.clone(), &[input.as_str()], r"SELECT regexp_extract_fancy(s, '^(a+)+(?=c)', 1) FROM t")
+        .await
+        .unwrap_err();
+    assert!(matches!(RegexpExtractError::find(&err), Some(RegexpExtractError::MatchFailed { .. })), "{err:?}");
+
+    let err = run_fancy(udf, &["abc"], "SELECT regexp_extract_fancy(s, '', 0) FROM t").await.unwrap_err();
+    assert!(matches!(RegexpExtractError::find(&err), Some(RegexpExtractError::EmptyPattern)), "{err:?}");
+    Ok(())
+}
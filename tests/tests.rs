@@ -1,12 +1,24 @@
 use std::sync::Arc;
-use datafusion::arrow::array::{Array, ArrayRef, StringArray};
-use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::arrow::array::{
+    Array, ArrayRef, FixedSizeListArray, Int32Array, Int64Array, ListArray, RunArray, StringArray,
+    StructArray, UInt64Array,
+};
+use datafusion::arrow::datatypes::{DataType, Field, Fields, Int32Type, Schema};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::datasource::MemTable;
 use datafusion::error::Result;
 use datafusion::prelude::*;
-use datafusion::logical_expr::ScalarUDF;
-use regexp_extract::register_regexp_extract_udf;
+use datafusion::logical_expr::{ColumnarValue, ScalarFunctionArgs, ScalarUDF};
+use datafusion::common::ScalarValue;
+use regexp_extract::{
+    ErrorMode, GroupOutOfRange, NoMatchMode, PostProcess, RegexCache, RegexpExtract, RegexpExtractBuilder,
+    RegexpExtractError, RegexpExtractOptions, RegexpFunctionFactory, all_udfs, capture_count, extract_all,
+    extract_all_from, regexp_extract_array, regexp_extract_expr, register_all_udfs, register_regexp_extract_all_udf,
+    register_regexp_extract_first_of_udf, register_regexp_extract_fixed_udf, register_regexp_extract_named_udf,
+    register_regexp_extract_span_udf, register_regexp_extract_template_udf,
+    register_regexp_extract_udf, register_regexp_extract_with_options,
+    register_regexp_group_count_udf, register_regexp_instr_end_udf, register_regexp_matches_udf,
+};
 
 fn string_table_batch(values: &[&str]) -> RecordBatch {
     let schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, true)]));
@@ -68,7 +80,7 @@ async fn test_regexp_extract_no_match() -> Result<()> {
     Ok(())
 }
 
-/// Test: invalid capture index -> expect NULL (or empty string, depending on semantics).
+/// Test: invalid capture index -> expect NULL, distinct from the "" of a no-match.
 #[tokio::test]
 async fn test_regexp_extract_invalid_index() -> Result<()> {
     let udf = register_regexp_extract_udf();
@@ -77,8 +89,7 @@ async fn test_regexp_extract_invalid_index() -> Result<()> {
     let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', 5) AS result FROM t"#;
     let out = run_and_first_string_col(&ctx, sql).await?;
     assert_eq!(out.len(), 1);
-    println!("{}", out.value(0));
-    assert_eq!(out.value(0), "", "expected empty for out-of-range group index");
+    assert!(out.is_null(0), "expected NULL for out-of-range group index");
     Ok(())
 }
 
@@ -127,6 +138,7 @@ async fn test_regexp_extract_case_insensitive() -> Result<()> {
 }
 
 /// Unicode: \p{L}+ matches letters in many languages
+#[cfg(not(feature = "regex-lite"))]
 #[tokio::test]
 async fn test_regexp_extract_unicode_letters() -> Result<()> {
     let udf = register_regexp_extract_udf();
@@ -198,3 +210,1217 @@ async fn test_regexp_extract_bigint_literal_signature_flex() -> Result<()> {
     assert_eq!(out.value(0), "abc");
     Ok(())
 }
+
+/// regexp_extract_all: one list entry per match of the group
+#[tokio::test]
+async fn test_regexp_extract_all_groups() -> Result<()> {
+    let udf = register_regexp_extract_all_udf();
+    let ctx = ctx_with_table(&["a1b22c333", "xyz"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract_all(s, '([a-z])(\d+)', 2) AS result FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let out = batches[0].column(0).as_any().downcast_ref::<ListArray>().unwrap();
+    assert_eq!(out.len(), 2);
+    let first = out.value(0);
+    let first = first.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(first.iter().collect::<Vec<_>>(), vec![Some("1"), Some("22"), Some("333")]);
+    assert_eq!(out.value(1).len(), 0);
+
+    let sql = r#"SELECT regexp_extract_all(s, '([a-z])', 3) AS result FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let out = batches[0].column(0).as_any().downcast_ref::<ListArray>().unwrap();
+    assert!(out.is_null(0) && out.is_null(1), "group out of range is a NULL list");
+    Ok(())
+}
+
+/// regexp_extract_named: planned schema is a struct with one field per named group
+#[tokio::test]
+async fn test_regexp_extract_named_planned_schema() -> Result<()> {
+    let udf = register_regexp_extract_named_udf();
+    let ctx = ctx_with_table(&["abc123", "!!!"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract_named(s, '(?<word>[a-z]+)(?<num>\d+)') AS result FROM t"#;
+    let df = ctx.sql(sql).await?;
+    let expected = DataType::Struct(Fields::from(vec![
+        Field::new("word", DataType::Utf8, true),
+        Field::new("num", DataType::Utf8, true),
+    ]));
+    assert_eq!(df.schema().field(0).data_type(), &expected);
+
+    let batches = df.collect().await?;
+    assert_eq!(batches[0].schema().field(0).data_type(), &expected);
+    let out = batches[0].column(0).as_any().downcast_ref::<StructArray>().unwrap();
+    let word = out.column_by_name("word").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+    let num = out.column_by_name("num").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(word.value(0), "abc");
+    assert_eq!(num.value(0), "123");
+    assert_eq!(word.value(1), "");
+    assert_eq!(num.value(1), "");
+    Ok(())
+}
+
+/// A pattern without named groups, or an empty one, fails while planning
+#[tokio::test]
+async fn test_regexp_extract_named_needs_named_group() -> Result<()> {
+    let udf = register_regexp_extract_named_udf();
+    let ctx = ctx_with_table(&["abc123"], &udf).await?;
+
+    let err = ctx.sql("SELECT regexp_extract_named(s, '([a-z]+)') FROM t").await.unwrap_err();
+    assert!(err.to_string().contains("has no named groups"), "unexpected error: {err}");
+    let err = ctx.sql("SELECT regexp_extract_named(s, '') FROM t").await.unwrap_err();
+    assert!(err.to_string().contains("Empty regex pattern"), "unexpected error: {err}");
+    Ok(())
+}
+
+/// Empty pattern is rejected instead of matching "" on every row
+#[tokio::test]
+async fn test_regexp_extract_empty_pattern() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc123"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '', 0) FROM t"#;
+    let err = run_and_first_string_col(&ctx, sql).await.unwrap_err();
+    assert!(err.to_string().contains("Empty regex pattern"), "unexpected error: {err}");
+    Ok(())
+}
+
+/// regexp_extract_all with the `o` flag returns overlapping matches
+#[tokio::test]
+async fn test_regexp_extract_all_overlapping() -> Result<()> {
+    let udf = register_regexp_extract_all_udf();
+    let ctx = ctx_with_table(&["aaa"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract_all(s, '(aa)', 1), regexp_extract_all(s, '(aa)', 1, 'o') FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let default = batches[0].column(0).as_any().downcast_ref::<ListArray>().unwrap();
+    let overlapping = batches[0].column(1).as_any().downcast_ref::<ListArray>().unwrap();
+    assert_eq!(default.value(0).len(), 1);
+    assert_eq!(overlapping.value(0).len(), 2);
+    let values = overlapping.value(0);
+    let values = values.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(values.iter().collect::<Vec<_>>(), vec![Some("aa"), Some("aa")]);
+    Ok(())
+}
+
+/// Anchored mode only matches when the pattern covers the whole value
+#[tokio::test]
+async fn test_regexp_extract_anchored() -> Result<()> {
+    let udf = RegexpExtractBuilder::new().anchored(true).build();
+    let ctx = ctx_with_table(&["abc123", "123"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '\d+', 0) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "", "whole string is not digits");
+    assert_eq!(out.value(1), "123");
+    Ok(())
+}
+
+/// Without anchoring a substring match is extracted
+#[tokio::test]
+async fn test_regexp_extract_unanchored() -> Result<()> {
+    let udf = RegexpExtractBuilder::new().anchored(false).build();
+    let ctx = ctx_with_table(&["abc123"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '\d+', 0) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "123");
+    Ok(())
+}
+
+/// regexp_instr_end reports character (not byte) positions on multibyte input
+#[tokio::test]
+async fn test_regexp_instr_end_unicode() -> Result<()> {
+    let udf = register_regexp_instr_end_udf();
+    let ctx = ctx_with_table(&["ÄÖÜabc123", "xyz"], &udf).await?;
+
+    let sql = r#"SELECT regexp_instr(s, '([a-z]+)(\d+)', 1, 1, '', 1), regexp_instr_end(s, '([a-z]+)(\d+)', 1) FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let start = batches[0].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    let end = batches[0].column(1).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(start.value(0), 4);
+    assert_eq!(end.value(0), 7);
+    assert_eq!(end.value(1), 0, "no match");
+    Ok(())
+}
+
+/// Group index given as UInt64, derived from an aggregate
+#[tokio::test]
+async fn test_regexp_extract_uint64_group_from_aggregate() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc123", "de45"], &udf).await?;
+
+    // COUNT(*) = 2 selects the digits group
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', g.c) FROM t
+                 CROSS JOIN (SELECT CAST(COUNT(*) AS BIGINT UNSIGNED) AS c FROM t) g"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.len(), 2);
+    assert_eq!(out.value(0), "123");
+    assert_eq!(out.value(1), "45");
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', CAST(1 AS BIGINT UNSIGNED)) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "abc");
+    Ok(())
+}
+
+/// The regex cache evicts least-recently-used patterns and never grows past its capacity
+#[test]
+fn test_regex_cache_bounded() {
+    let cache = RegexCache::new(4);
+    for i in 0..20 {
+        cache.get_or_compile(&format!("a{{{i}}}")).unwrap();
+        assert!(cache.len() <= 4);
+    }
+    assert_eq!(cache.len(), 4);
+
+    // re-requesting a cached pattern does not add an entry; invalid ones are not cached
+    cache.get_or_compile("a{19}").unwrap();
+    assert_eq!(cache.len(), 4);
+    assert!(cache.get_or_compile("(").is_err());
+    assert_eq!(cache.len(), 4);
+}
+
+/// A UDF built with a small cache still extracts correctly across many patterns
+#[tokio::test]
+async fn test_regexp_extract_small_cache_capacity() -> Result<()> {
+    let udf = RegexpExtractBuilder::new().cache_capacity(1).build();
+    let ctx = ctx_with_table(&["abc123"], &udf).await?;
+
+    for (pattern, expected) in [(r"(\d+)", "123"), (r"([a-z]+)", "abc"), (r"(\d+)", "123")] {
+        let sql = format!("SELECT regexp_extract(s, '{pattern}', 1) FROM t");
+        let out = run_and_first_string_col(&ctx, &sql).await?;
+        assert_eq!(out.value(0), expected);
+    }
+    Ok(())
+}
+
+/// The `i` flag applies Unicode simple case folding: sharp s folds to capital sharp s, not "SS"
+#[cfg(not(feature = "regex-lite"))]
+#[tokio::test]
+async fn test_regexp_extract_case_insensitive_flag_unicode() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["STRAẞE", "STRASSE", "straße"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, 'straße', 0, 'i') FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "STRAẞE");
+    assert_eq!(out.value(1), "", "no full case folding of ß to SS");
+    assert_eq!(out.value(2), "straße");
+
+    let sql = r#"SELECT regexp_extract(s, 'straße', 0) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "", "case-sensitive without the flag");
+    Ok(())
+}
+
+/// Unknown flag characters are rejected
+#[tokio::test]
+async fn test_regexp_extract_unknown_flag() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, 'a', 0, 'q') FROM t"#;
+    let err = run_and_first_string_col(&ctx, sql).await.unwrap_err();
+    assert!(err.to_string().contains("Unknown regex flag 'q'"), "unexpected error: {err}");
+
+    // `o` only means something to regexp_extract_all
+    let sql = r#"SELECT regexp_extract(s, 'a', 0, 'io') FROM t"#;
+    let err = run_and_first_string_col(&ctx, sql).await.unwrap_err();
+    assert!(err.to_string().contains("Unknown regex flag 'o'"), "unexpected error: {err}");
+    Ok(())
+}
+
+/// A group beyond the pattern's groups is NULL; a valid optional group that did not match is ""
+#[tokio::test]
+async fn test_regexp_extract_out_of_range_vs_non_participating() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)?', 5), regexp_extract(s, '([a-z]+)(\d+)?', 2) FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let out_of_range = batches[0].column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let non_participating = batches[0].column(1).as_any().downcast_ref::<StringArray>().unwrap();
+    assert!(out_of_range.is_null(0));
+    assert!(!non_participating.is_null(0));
+    assert_eq!(non_participating.value(0), "");
+    Ok(())
+}
+
+/// With GroupOutOfRange::Error an out-of-range group fails, a non-participating one does not
+#[tokio::test]
+async fn test_regexp_extract_out_of_range_error_mode() -> Result<()> {
+    let udf = RegexpExtractBuilder::new().group_out_of_range(GroupOutOfRange::Error).build();
+    let ctx = ctx_with_table(&["abc"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)?', 5) FROM t"#;
+    let err = run_and_first_string_col(&ctx, sql).await.unwrap_err();
+    assert!(err.to_string().contains("Group index 5 out of range"), "unexpected error: {err}");
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)?', 2) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "");
+    Ok(())
+}
+
+/// regexp_extract_array runs the extraction on an Arrow array without a SessionContext
+#[test]
+fn test_regexp_extract_array_direct() -> Result<()> {
+    let input = StringArray::from(vec![Some("a1"), None, Some("bb22")]);
+    let out = regexp_extract_array(&input, r"([a-z]+)(\d+)", 2)?;
+    assert_eq!(out.len(), 3);
+    assert_eq!(out.value(0), "1");
+    assert_eq!(out.value(1), "");
+    assert_eq!(out.value(2), "22");
+
+    assert!(regexp_extract_array(&input, r"([a-z]+", 1).is_err());
+    Ok(())
+}
+
+/// The output's value buffer is reserved once from the input's bytes instead of regrowing
+#[test]
+fn test_regexp_extract_value_capacity() -> Result<()> {
+    let rows = (0..10_000).map(|i| format!("row{i}")).collect::<Vec<_>>();
+    let input = StringArray::from(rows.iter().map(String::as_str).collect::<Vec<_>>());
+    for input in [input.clone(), input.slice(2_500, 5_000)] {
+        let out = regexp_extract_array(&input, r"(.*)", 1)?;
+        let (bytes, capacity) = (out.values().len(), out.values().capacity());
+        assert_eq!(bytes, input.iter().map(|v| v.unwrap().len()).sum::<usize>());
+        // arrow rounds allocations up to 64 bytes; doubling from empty would overshoot further
+        assert!(capacity >= bytes && capacity < bytes + 64, "{bytes} bytes in a {capacity} byte buffer");
+    }
+    Ok(())
+}
+
+/// Empty inputs take the short-circuit path but keep the same results
+#[test]
+fn test_regexp_extract_empty_inputs() -> Result<()> {
+    let input = StringArray::from(vec![Some(""), Some("a1"), None, Some("")]);
+    let out = regexp_extract_array(&input, r"([a-z]+)(\d+)", 2)?;
+    assert_eq!(out.iter().collect::<Vec<_>>(), vec![Some(""), Some("1"), Some(""), Some("")]);
+
+    // a pattern that can match "" still reports its (empty) match on empty rows
+    let out = regexp_extract_array(&input, r"(\d*)x?", 1)?;
+    assert_eq!(out.iter().collect::<Vec<_>>(), vec![Some(""), Some(""), Some(""), Some("")]);
+
+    // out-of-range groups stay NULL on empty rows
+    let out = regexp_extract_array(&input, r"(\d+)", 3)?;
+    assert!(out.is_null(0) && out.is_null(3));
+    Ok(())
+}
+
+/// Fixed-arity patterns take the `Captures::extract` path and alternations, where a group may
+/// not take part, the generic one; both match plain capture lookup by group index
+#[test]
+fn test_regexp_extract_fixed_arity_unchanged() -> Result<()> {
+    let rows = vec![Some("abc123def"), Some("x9"), Some("nothing"), None, Some("a1b2c3d4"), Some("b"), Some("a")];
+    let input = StringArray::from(rows.clone());
+    let patterns = [
+        r"([a-z]+)(\d+)",             // 2 fixed groups
+        r"(\w)(\w)(\w)(\w)",          // 4 fixed groups
+        r"([a-z])(\d)([a-z])(\d)(.)", // 5 groups: generic path
+        r"([a-z]+)(\d+)?",            // optional group: generic path
+        r"(a)|(b)",                   // one group per branch: generic path
+        r"(\d)(x)|([a-z])(\d)",       // two groups per branch: generic path
+    ];
+    for pattern in patterns {
+        let regex = regex::Regex::new(pattern).unwrap();
+        for group in 0..regex.captures_len() {
+            let out = regexp_extract_array(&input, pattern, group)?;
+            for (row, s) in rows.iter().enumerate() {
+                let expected = regex.captures(s.unwrap_or_default())
+                    .and_then(|cap| cap.get(group))
+                    .map_or("", |m| m.as_str());
+                assert_eq!(out.value(row), expected, "pattern {pattern} group {group} row {row}");
+            }
+        }
+    }
+    assert_eq!(extract_all(&[Some("abc"), Some("b")], "(a)|(b)", 2).unwrap(), vec![Some(String::new()), Some("b".to_string())]);
+    Ok(())
+}
+
+/// Records the message of every tracing event, for the cache event tests below
+#[cfg(feature = "tracing")]
+mod capture {
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    #[derive(Default)]
+    pub struct Capture(pub Arc<Mutex<Vec<String>>>);
+
+    struct Message<'a>(&'a mut String);
+    impl Visit for Message<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                *self.0 = format!("{value:?}");
+            }
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+        fn new_span(&self, _: &Attributes<'_>) -> Id { Id::from_u64(1) }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut message = String::new();
+            event.record(&mut Message(&mut message));
+            self.0.lock().unwrap().push(message);
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+}
+
+/// With the `tracing` feature, the cache reports a miss on first compile and a hit afterwards
+#[cfg(feature = "tracing")]
+#[test]
+fn test_regex_cache_tracing_events() {
+    use std::sync::Mutex;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let cache = RegexCache::new(4);
+    tracing::subscriber::with_default(capture::Capture(events.clone()), || {
+        cache.get_or_compile(r"(\d+)").unwrap();
+        cache.get_or_compile(r"(\d+)").unwrap();
+    });
+    assert_eq!(*events.lock().unwrap(), vec!["regex cache miss", "regex cache hit"]);
+}
+
+/// A literal pattern is compiled into the pinned slot while planning, which reports the miss,
+/// and every batch then reports a hit on it
+#[cfg(feature = "tracing")]
+#[test]
+fn test_regex_cache_tracing_events_pinned() -> Result<()> {
+    use std::sync::Mutex;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let udf = register_regexp_extract_udf();
+    // A current-thread runtime keeps the whole query on the thread the subscriber is set for.
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    let out = tracing::subscriber::with_default(capture::Capture(events.clone()), || {
+        runtime.block_on(async {
+            let ctx = ctx_with_table(&["abc123", "def456"], &udf).await?;
+            run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '(\d+)', 1) FROM t"#).await
+        })
+    })?;
+    assert_eq!(out.value(1), "456");
+
+    let events = events.lock().unwrap();
+    let cache_events = events.iter().filter(|e| e.starts_with("regex cache")).collect::<Vec<_>>();
+    assert_eq!(cache_events.first().map(|e| e.as_str()), Some("regex cache miss"), "{events:?}");
+    assert!(cache_events.len() > 1 && cache_events[1..].iter().all(|e| *e == "regex cache hit"), "{events:?}");
+    assert_eq!(udf.inner().as_any().downcast_ref::<RegexpExtract>().unwrap().cache().compilations(), 1);
+    Ok(())
+}
+
+/// extract_all: the extraction core on plain Rust values
+#[test]
+fn test_extract_all_plain_values() {
+    let inputs = [Some("abc123"), Some("abc"), None];
+    let out = extract_all(&inputs, r"([a-z]+)(\d+)", 2).unwrap();
+    assert_eq!(out, vec![Some("123".to_string()), Some(String::new()), Some(String::new())]);
+
+    let out = extract_all(&inputs, r"([a-z]+)(\d+)", 3).unwrap();
+    assert_eq!(out, vec![None, None, None], "group out of range");
+
+    assert!(matches!(extract_all(&inputs, r"([a-z]+", 1), Err(RegexpExtractError::InvalidPattern { .. })));
+    assert!(matches!(extract_all(&inputs, "", 0), Err(RegexpExtractError::EmptyPattern)));
+}
+
+/// Array input with scalar pattern and group: output has one row per input row
+#[tokio::test]
+async fn test_regexp_extract_mixed_scalar_array_args() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["a1", "b2", "c3", "d4", "e5"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '(\d)', 1) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.len(), 5);
+    assert_eq!(out.value(4), "5");
+
+    // scalar input broadcast against a per-row group column
+    let sql = r#"SELECT regexp_extract('x9', '([a-z])(\d)', g.c) FROM t
+                 CROSS JOIN (SELECT CAST(2 AS BIGINT UNSIGNED) AS c) g"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let values = batches.iter()
+        .flat_map(|b| b.column(0).as_any().downcast_ref::<StringArray>().unwrap().iter().map(|v| v.map(str::to_string)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec![Some("9".to_string()); 5]);
+
+    // an invalid pattern still yields one (empty) row per input row
+    let sql = r#"SELECT regexp_extract(s, '(', 1) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.len(), 5);
+    Ok(())
+}
+
+/// A signed group column works like an unsigned one
+#[tokio::test]
+async fn test_regexp_extract_int64_group_column() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["unused"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', g) FROM (VALUES ('abc123', 1), ('x9', 2), ('abc123', 5)) AS v(s, g)"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.iter().collect::<Vec<_>>(), vec![Some("abc"), Some("9"), None]);
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', g) FROM (VALUES ('abc123', 1), ('x9', -1)) AS v(s, g)"#;
+    let err = run_and_first_string_col(&ctx, sql).await.unwrap_err();
+    assert!(err.to_string().contains("must not be negative"), "unexpected error: {err}");
+    Ok(())
+}
+
+/// Boolean case_insensitive overload
+#[tokio::test]
+async fn test_regexp_extract_case_insensitive_bool_arg() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["APPLE9"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, 'apple(\d+)', 1, true), regexp_extract(s, 'apple(\d+)', 1, false) FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let insensitive = batches[0].column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let sensitive = batches[0].column(1).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(insensitive.value(0), "9");
+    assert_eq!(sensitive.value(0), "");
+    Ok(())
+}
+
+/// Same pattern: a non-matching row is "", an out-of-range group is NULL
+#[tokio::test]
+async fn test_regexp_extract_no_match_vs_out_of_range() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc123", "!!!"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', 2), regexp_extract(s, '([a-z]+)(\d+)', 3) FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let valid = batches[0].column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let out_of_range = batches[0].column(1).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(valid.value(0), "123");
+    assert!(!valid.is_null(1));
+    assert_eq!(valid.value(1), "", "no match is empty, not NULL");
+    assert!(out_of_range.is_null(0) && out_of_range.is_null(1));
+    Ok(())
+}
+
+/// multi_line: `^` anchors at each line, so the first matching line supplies the key
+#[tokio::test]
+async fn test_regexp_extract_multi_line_first_line() -> Result<()> {
+    let udf = RegexpExtractBuilder::new().multi_line(true).build();
+    let ctx = ctx_with_table(&["alpha: 1\nbeta: 2", "# comment\ngamma: 3", "no keys\nhere"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '^(\w+):', 1) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "alpha");
+    assert_eq!(out.value(1), "gamma");
+    assert_eq!(out.value(2), "");
+
+    // without multi_line `^` only matches at the start of the value
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["# comment\ngamma: 3"], &udf).await?;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "");
+    Ok(())
+}
+
+/// Five-row table: output unchanged after the request stopped cloning its input
+#[tokio::test]
+async fn test_regexp_extract_five_rows() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["a1", "bb22", "", "ccc333", "d"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d*)', 1) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.iter().collect::<Vec<_>>(), vec![Some("a"), Some("bb"), Some(""), Some("ccc"), Some("d")]);
+    Ok(())
+}
+
+/// The `m` flag lets `^...$` match an interior line of a multi-line cell
+#[tokio::test]
+async fn test_regexp_extract_multi_line_flag() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["INFO 1\nERROR 42\nINFO 3"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '^ERROR (\d+)$', 1, 'm'), regexp_extract(s, '^ERROR (\d+)$', 1) FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let multi_line = batches[0].column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    let single_line = batches[0].column(1).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(multi_line.value(0), "42");
+    assert_eq!(single_line.value(0), "");
+    Ok(())
+}
+
+/// CREATE FUNCTION through the function factory registers the UDF on the session state
+#[tokio::test]
+async fn test_regexp_function_factory_create_function() -> Result<()> {
+    let ctx = SessionContext::new().with_function_factory(Arc::new(RegexpFunctionFactory));
+    assert!(!ctx.state().scalar_functions().contains_key("regexp_extract_all"));
+
+    ctx.sql("CREATE FUNCTION regexp_extract_all(VARCHAR, VARCHAR, BIGINT) RETURNS VARCHAR LANGUAGE RUST AS 'regexp_extract_all'")
+        .await?.collect().await?;
+    let udf = ctx.state().scalar_functions().get("regexp_extract_all").cloned().expect("registered by the factory");
+    assert_eq!(udf.name(), "regexp_extract_all");
+
+    let batches = ctx.sql(r#"SELECT regexp_extract_all('a1b2', '(\d)', 1)"#).await?.collect().await?;
+    assert_eq!(batches[0].column(0).as_any().downcast_ref::<ListArray>().unwrap().value(0).len(), 2);
+
+    let err = ctx.sql("CREATE FUNCTION nope(VARCHAR) RETURNS VARCHAR LANGUAGE RUST AS 'nope'").await.unwrap_err();
+    assert!(err.to_string().contains("No regexp function named nope"), "unexpected error: {err}");
+    Ok(())
+}
+
+/// register_all_udfs installs every function of the crate
+#[tokio::test]
+async fn test_register_all_udfs() -> Result<()> {
+    let ctx = SessionContext::new();
+    register_all_udfs(&ctx);
+    for udf in all_udfs() {
+        assert!(ctx.state().scalar_functions().contains_key(udf.name()), "{} missing", udf.name());
+    }
+    Ok(())
+}
+
+/// regexp_extract_span returns value and character span; no-match rows are all NULL
+#[tokio::test]
+async fn test_regexp_extract_span_fields() -> Result<()> {
+    let udf = register_regexp_extract_span_udf();
+    let ctx = ctx_with_table(&["ÄÖabc123", "xyz"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract_span(s, '([a-z]+)(\d+)', 2) FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let out = batches[0].column(0).as_any().downcast_ref::<StructArray>().unwrap();
+    let value = out.column_by_name("value").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+    let start = out.column_by_name("start").unwrap().as_any().downcast_ref::<Int64Array>().unwrap();
+    let end = out.column_by_name("end").unwrap().as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(value.value(0), "123");
+    assert_eq!(start.value(0), 6);
+    assert_eq!(end.value(0), 9);
+    assert!(value.is_null(1) && start.is_null(1) && end.is_null(1));
+    Ok(())
+}
+
+/// A literal pattern is compiled once while planning, not once per batch
+#[tokio::test]
+async fn test_literal_pattern_compiled_once_per_plan() -> Result<()> {
+    let udf = RegexpExtractBuilder::new().cache_capacity(0).build();
+    let ctx = SessionContext::new();
+    ctx.register_udf(udf.clone());
+    let batches = vec![
+        string_table_batch(&["abc123"]),
+        string_table_batch(&["def456"]),
+        string_table_batch(&["ghi789"]),
+    ];
+    let mem = MemTable::try_new(batches[0].schema(), vec![batches])?;
+    ctx.register_table("t", Arc::new(mem))?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', 2) FROM t"#;
+    let out = ctx.sql(sql).await?.collect().await?;
+    assert_eq!(out.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+
+    let extract = udf.inner().as_any().downcast_ref::<RegexpExtract>().unwrap();
+    assert_eq!(extract.cache().compilations(), 1);
+    Ok(())
+}
+
+/// Without a group argument the first group is extracted
+#[tokio::test]
+async fn test_regexp_extract_default_group() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc123", "xyz"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)') FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "abc");
+    assert_eq!(out.value(1), "");
+    Ok(())
+}
+
+/// A call with the wrong number of arguments names every accepted form
+#[tokio::test]
+async fn test_regexp_extract_wrong_arity_lists_signatures() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc123"], &udf).await?;
+
+    let err = ctx.sql("SELECT regexp_extract(s) FROM t").await.unwrap_err().to_string();
+    for form in [
+        "regexp_extract(Utf8, Utf8)",
+        "regexp_extract(Utf8, Utf8, Int64)",
+        "regexp_extract(Utf8, Utf8, Int64, Utf8)",
+        "regexp_extract(Utf8, Utf8, Int64, Boolean)",
+    ] {
+        assert!(err.contains(&format!("\t{form}\n")) || err.ends_with(&format!("\t{form}")), "{form} missing from: {err}");
+    }
+    Ok(())
+}
+
+/// A start position past the first number extracts the second one
+#[tokio::test]
+async fn test_regexp_extract_start_position() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["ä12 ö345", "12"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '(\d+)', 1, 4) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "345");
+    assert_eq!(out.value(1), "");
+
+    // the boolean case_insensitive form takes a start position too
+    let ctx = ctx_with_table(&["a0 A1 a2"], &udf).await?;
+    let sql = r#"SELECT regexp_extract(s, '(a\d)', 1, true, 3), regexp_extract(s, '(a\d)', 1, false, 3) FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let column = |i: usize| batches[0].column(i).as_any().downcast_ref::<StringArray>().unwrap().value(0).to_string();
+    assert_eq!((column(0), column(1)), ("A1".to_string(), "a2".to_string()));
+    Ok(())
+}
+
+/// The x flag ignores whitespace and comments; an escaped space still matches a space
+#[tokio::test]
+async fn test_regexp_extract_verbose_flag() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["order no 42", "orderno 42"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '
+        order\ no   # the literal words, space escaped
+        \s*
+        (\d+)       # the order number
+    ', 1, 'x') FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "42");
+    assert_eq!(out.value(1), "");
+    Ok(())
+}
+
+/// Anchoring still applies when a verbose pattern ends in a comment
+#[tokio::test]
+async fn test_anchored_verbose_trailing_comment() -> Result<()> {
+    let udf = RegexpExtractBuilder::new().anchored(true).build();
+    let ctx = ctx_with_table(&["abc123", "abc123!"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+) (\d+) # letters then digits', 2, 'x') FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "123");
+    assert_eq!(out.value(1), "");
+    Ok(())
+}
+
+/// Extraction over many repeated values matches extracting each row on its own
+#[tokio::test]
+async fn test_regexp_extract_high_duplication() -> Result<()> {
+    let values = ["alpha-1", "beta-22", "gamma", "", "delta-4444"];
+    let rows = (0..5000).map(|i| values[(i * 7 + i / 3) % values.len()]).collect::<Vec<&str>>();
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&rows, &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)-(\d+)', 2) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+
+    // The naive path: the regex crate on every row, without any deduplication.
+    let re = regex::Regex::new(r"([a-z]+)-(\d+)").unwrap();
+    assert_eq!(out.len(), rows.len());
+    for (row, s) in rows.iter().enumerate() {
+        let expected = re.captures(s).map_or("", |c| c.get(2).unwrap().as_str());
+        assert_eq!(out.value(row), expected, "row {row}");
+    }
+    Ok(())
+}
+
+/// Calls `udf` directly, bypassing planning, as DataFusion would for one batch.
+fn invoke_regexp_extract(udf: &ScalarUDF, args: Vec<ColumnarValue>, number_rows: usize) -> Result<ColumnarValue> {
+    let arg_fields = args
+        .iter()
+        .map(|arg| Arc::new(Field::new("arg", arg.data_type(), true)))
+        .collect();
+    udf.invoke_with_args(ScalarFunctionArgs {
+        args,
+        arg_fields,
+        number_rows,
+        return_field: Arc::new(Field::new("regexp_extract", DataType::Utf8, true)),
+    })
+}
+
+/// Scalar arguments give a scalar result, an array input an array
+#[test]
+fn test_regexp_extract_scalar_in_scalar_out() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let args = |input| vec![
+        input,
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(r"([a-z]+)(\d+)".to_string()))),
+        ColumnarValue::Scalar(ScalarValue::Int64(Some(2))),
+    ];
+    let input = ColumnarValue::Scalar(ScalarValue::Utf8(Some("abc123".to_string())));
+    match invoke_regexp_extract(&udf, args(input), 3)? {
+        ColumnarValue::Scalar(value) => assert_eq!(value, ScalarValue::Utf8(Some("123".to_string()))),
+        ColumnarValue::Array(_) => panic!("expected a scalar"),
+    }
+
+    let input = ColumnarValue::Array(Arc::new(StringArray::from(vec!["abc123", "xyz"])));
+    match invoke_regexp_extract(&udf, args(input), 2)? {
+        ColumnarValue::Array(array) => assert_eq!(array.len(), 2),
+        ColumnarValue::Scalar(_) => panic!("expected an array"),
+    }
+    Ok(())
+}
+
+/// capture_count includes group 0; an invalid pattern is an error
+#[test]
+fn test_capture_count() {
+    assert_eq!(capture_count(r"([a-z]+)(\d+)").unwrap(), 3);
+    assert_eq!(capture_count("abc").unwrap(), 1);
+    assert!(capture_count("(unclosed").is_err());
+}
+
+/// regexp_group_count counts capturing groups; an invalid pattern is an error
+#[tokio::test]
+async fn test_regexp_group_count() -> Result<()> {
+    let ctx = SessionContext::new();
+    ctx.register_udf(register_regexp_group_count_udf());
+
+    let batches = ctx.sql(r#"SELECT regexp_group_count('(\d+)-(\d+)')"#).await?.collect().await?;
+    let out = batches[0].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+    assert_eq!(out.value(0), 2);
+
+    let result = match ctx.sql("SELECT regexp_group_count('(unclosed')").await {
+        Ok(df) => df.collect().await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+    assert!(result.unwrap_err().to_string().contains("Invalid regex pattern"));
+    Ok(())
+}
+
+/// Zero-width matches next to multibyte characters never split a codepoint
+#[tokio::test]
+async fn test_zero_width_matches_on_multibyte_input() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["αβγ123"], &udf).await?;
+    ctx.register_udf(register_regexp_extract_all_udf());
+
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '(\d*)', 1) FROM t"#).await?;
+    assert_eq!(out.value(0), "");
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '(\d*)', 1, 3) FROM t"#).await?;
+    assert_eq!(out.value(0), "");
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '(\d*)', 1, 4) FROM t"#).await?;
+    assert_eq!(out.value(0), "123");
+
+    for (flags, expected) in [
+        ("", vec!["", "", "", "123"]),
+        ("o", vec!["", "", "", "123", "23", "3", ""]),
+    ] {
+        let sql = format!(r#"SELECT regexp_extract_all(s, '(\d*)', 1, '{flags}') FROM t"#);
+        let batches = ctx.sql(&sql).await?.collect().await?;
+        let list = batches[0].column(0).as_any().downcast_ref::<ListArray>().unwrap();
+        let row = list.value(0);
+        let values = row.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(values.iter().map(Option::unwrap).collect::<Vec<&str>>(), expected, "flags '{flags}'");
+    }
+    Ok(())
+}
+
+/// regexp_matches lists every full match; NULL input gives a NULL list
+#[tokio::test]
+async fn test_regexp_matches() -> Result<()> {
+    let udf = register_regexp_matches_udf();
+    let ctx = ctx_with_table(&["a bb ccc", "!!"], &udf).await?;
+
+    let sql = r#"SELECT regexp_matches(s, '\w+') FROM t UNION ALL SELECT regexp_matches(NULL, '\w+')"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let lists = batches
+        .iter()
+        .flat_map(|b| {
+            let list = b.column(0).as_any().downcast_ref::<ListArray>().unwrap().clone();
+            (0..list.len()).map(move |i| {
+                (!list.is_null(i)).then(|| {
+                    let row = list.value(i);
+                    let values = row.as_any().downcast_ref::<StringArray>().unwrap();
+                    values.iter().map(|v| v.unwrap().to_string()).collect::<Vec<String>>()
+                })
+            })
+        })
+        .collect::<Vec<Option<Vec<String>>>>();
+    assert!(lists.contains(&Some(vec!["a".to_string(), "bb".to_string(), "ccc".to_string()])));
+    assert!(lists.contains(&Some(vec![])));
+    assert!(lists.contains(&None));
+    Ok(())
+}
+
+/// regexp_extract_template expands the template against the first match only
+#[tokio::test]
+async fn test_regexp_extract_template() -> Result<()> {
+    let udf = register_regexp_extract_template_udf();
+    let ctx = ctx_with_table(&["ab12 cd34", "!!"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract_template(s, '([a-z]+)(\d+)', '$2-$1') FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    assert_eq!(out.value(0), "12-ab");
+    assert_eq!(out.value(1), "");
+    Ok(())
+}
+
+/// Run-end encoded input is extracted per run and expanded back to one value per row
+#[tokio::test]
+async fn test_regexp_extract_run_end_encoded_input() -> Result<()> {
+    let values = StringArray::from(vec![Some("ab12"), None, Some("cd345"), Some("!!")]);
+    let runs = RunArray::<Int32Type>::try_new(&Int32Array::from(vec![2, 3, 6, 7]), &values)?;
+    let schema = Arc::new(Schema::new(vec![Field::new("s", runs.data_type().clone(), true)]));
+    let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(runs)])?;
+
+    let ctx = SessionContext::new();
+    ctx.register_udf(register_regexp_extract_udf());
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', 2) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+    let out = out.iter().map(Option::unwrap).collect::<Vec<&str>>();
+    assert_eq!(out, vec!["12", "12", "", "345", "345", "345", ""]);
+    Ok(())
+}
+
+/// Non-default options: NULL on no-match, and an error for an invalid pattern
+#[tokio::test]
+async fn test_regexp_extract_with_options() -> Result<()> {
+    let udf = register_regexp_extract_with_options(RegexpExtractOptions {
+        on_no_match: NoMatchMode::Null,
+        ..Default::default()
+    });
+    let ctx = ctx_with_table(&["abc123", "xyz", "abc"], &udf).await?;
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '([a-z]+)(\d*)', 2) FROM t"#).await?;
+    assert_eq!(out.value(0), "123");
+    assert!(out.is_valid(2) && out.value(2).is_empty());
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '(\d+)', 1) FROM t"#).await?;
+    assert!(out.is_null(1) && out.is_null(2));
+    let out = run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '(unclosed', 1) FROM t").await?;
+    assert_eq!(out.null_count(), 3);
+
+    let udf = register_regexp_extract_with_options(RegexpExtractOptions {
+        on_invalid_pattern: ErrorMode::Error,
+        group_out_of_range: GroupOutOfRange::Error,
+        ..Default::default()
+    });
+    let ctx = ctx_with_table(&["abc123"], &udf).await?;
+    let err = run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '(unclosed', 1) FROM t").await.unwrap_err();
+    assert!(err.to_string().contains("Invalid regex pattern"), "{err}");
+    assert!(run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '(a)', 2) FROM t").await.is_err());
+    let out = run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '(x)', 1) FROM t").await?;
+    assert_eq!(out.value(0), "");
+    Ok(())
+}
+
+/// Two differently configured instances in one context keep their own policies
+#[tokio::test]
+async fn test_per_instance_policies() -> Result<()> {
+    let lenient = register_regexp_extract_udf();
+    let strict = RegexpExtractBuilder::new()
+        .name("regexp_extract_strict")
+        .on_no_match(NoMatchMode::Null)
+        .on_invalid_pattern(ErrorMode::Error)
+        .build();
+    let ctx = ctx_with_table(&["abc123", "xyz"], &lenient).await?;
+    ctx.register_udf(strict);
+
+    let sql = r#"SELECT regexp_extract(s, '(\d+)', 1), regexp_extract_strict(s, '(\d+)', 1) FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let col = |i: usize| batches[0].column(i).as_any().downcast_ref::<StringArray>().unwrap().clone();
+    assert_eq!((col(0).value(0), col(1).value(0)), ("123", "123"));
+    assert!(col(0).is_valid(1) && col(0).value(1).is_empty());
+    assert!(col(1).is_null(1));
+
+    let out = run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '(unclosed', 1) FROM t").await?;
+    assert_eq!(out.value(0), "");
+    let err = run_and_first_string_col(&ctx, "SELECT regexp_extract_strict(s, '(unclosed', 1) FROM t").await;
+    assert!(err.is_err());
+    Ok(())
+}
+
+/// A group index above u32::MAX is never truncated into a valid group
+#[tokio::test]
+async fn test_group_index_above_u32_max() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc123"], &udf).await?;
+
+    // 2^32 + 1 would wrap to group 1 if truncated to 32 bits.
+    let result = run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '([a-z]+)', 4294967297) FROM t").await;
+    #[cfg(target_pointer_width = "64")]
+    assert!(result?.is_null(0));
+    #[cfg(not(target_pointer_width = "64"))]
+    assert!(result.unwrap_err().to_string().contains("does not fit in usize"));
+    Ok(())
+}
+
+/// A negative group index is an error
+#[tokio::test]
+async fn test_negative_group_index() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc123"], &udf).await?;
+
+    let err = run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '([a-z]+)', -1) FROM t").await.unwrap_err();
+    assert!(err.to_string().contains("must not be negative"), "{err}");
+    Ok(())
+}
+
+/// An unsigned UInt32 group literal is accepted, through SQL and passed as is
+#[tokio::test]
+async fn test_regexp_extract_uint32_group() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc123"], &udf).await?;
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', arrow_cast(2, 'UInt32')) FROM t"#;
+    assert_eq!(run_and_first_string_col(&ctx, sql).await?.value(0), "123");
+
+    let args = vec![
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some("abc123".to_string()))),
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(r"([a-z]+)(\d+)".to_string()))),
+        ColumnarValue::Scalar(ScalarValue::UInt32(Some(2))),
+    ];
+    match invoke_regexp_extract(&udf, args, 1)? {
+        ColumnarValue::Scalar(value) => assert_eq!(value, ScalarValue::Utf8(Some("123".to_string()))),
+        ColumnarValue::Array(_) => panic!("expected a scalar"),
+    }
+    Ok(())
+}
+
+/// A large batch of distinct values gives the same output as the regex crate row by row
+#[tokio::test]
+async fn test_regexp_extract_large_batch() -> Result<()> {
+    let rows = (0..8_000)
+        .map(|i| if i % 3 == 0 { format!("row{i}") } else { format!("no digits here {}", "x".repeat(i % 50)) })
+        .collect::<Vec<String>>();
+    let rows = rows.iter().map(String::as_str).collect::<Vec<&str>>();
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&rows, &udf).await?;
+
+    let sql = r#"SELECT regexp_extract(s, '([a-z]+)(\d+)', 2) FROM t"#;
+    let out = run_and_first_string_col(&ctx, sql).await?;
+
+    let re = regex::Regex::new(r"([a-z]+)(\d+)").unwrap();
+    assert_eq!(out.len(), rows.len());
+    for (row, s) in rows.iter().enumerate() {
+        let expected = re.captures(s).map_or("", |c| c.get(2).unwrap().as_str());
+        assert_eq!(out.value(row), expected, "row {row}");
+    }
+    Ok(())
+}
+
+/// extract_all_from runs the extraction over a plain slice
+#[test]
+fn test_extract_all_from() -> Result<()> {
+    let out = extract_all_from(&["a1", "bb", "c33"], r"([a-z])(\d+)", 1)?;
+    assert_eq!(out, vec![Some("a".to_string()), Some(String::new()), Some("c".to_string())]);
+    assert_eq!(extract_all_from(&["a1"], r"(\d)", 2)?, vec![None]);
+    assert!(extract_all_from(&["a1"], "(unclosed", 1).is_err());
+    Ok(())
+}
+
+/// A commented verbose date pattern keeps its group numbering, via the flag or the builder
+#[tokio::test]
+async fn test_verbose_date_pattern() -> Result<()> {
+    let pattern = r#"'
+        (\d{4})   # 1: year
+        -
+        (\d{2})   # 2: month
+        -
+        (\d{2})   # 3: day
+    '"#;
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["due 2024-03-17", "no date"], &udf).await?;
+    let sql = format!("SELECT regexp_extract(s, {pattern}, 2, 'x') FROM t");
+    let out = run_and_first_string_col(&ctx, &sql).await?;
+    assert_eq!(out.value(0), "03");
+    assert_eq!(out.value(1), "");
+
+    let udf = RegexpExtractBuilder::new().ignore_whitespace(true).build();
+    let ctx = ctx_with_table(&["due 2024-03-17"], &udf).await?;
+    let sql = format!("SELECT regexp_extract(s, {pattern}, 3) FROM t");
+    assert_eq!(run_and_first_string_col(&ctx, &sql).await?.value(0), "17");
+    Ok(())
+}
+
+/// regexp_extract_fixed has one slot per group; a no-match row has all slots NULL
+#[tokio::test]
+async fn test_regexp_extract_fixed() -> Result<()> {
+    let udf = register_regexp_extract_fixed_udf();
+    let ctx = ctx_with_table(&["12-ab", "nope"], &udf).await?;
+
+    let sql = r#"SELECT regexp_extract_fixed(s, '(\d+)-(\w+)') FROM t"#;
+    let batches = ctx.sql(sql).await?.collect().await?;
+    let out = batches[0].column(0).as_any().downcast_ref::<FixedSizeListArray>().unwrap();
+    assert_eq!(out.value_length(), 2);
+    let first = out.value(0);
+    let first = first.as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!((first.value(0), first.value(1)), ("12", "ab"));
+    let second = out.value(1);
+    assert_eq!(second.null_count(), 2);
+    Ok(())
+}
+
+/// The t flag trims each row before matching, so anchors see the trimmed text
+#[tokio::test]
+async fn test_regexp_extract_trim_flag() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["  123  "], &udf).await?;
+
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '^(\d+)$', 1) FROM t"#).await?;
+    assert_eq!(out.value(0), "");
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '^(\d+)$', 1, 't') FROM t"#).await?;
+    assert_eq!(out.value(0), "123");
+    Ok(())
+}
+
+/// Array arguments of different lengths are rejected with a descriptive error
+#[test]
+fn test_mismatched_argument_lengths() {
+    let udf = register_regexp_extract_udf();
+    let args = vec![
+        ColumnarValue::Array(Arc::new(StringArray::from(vec!["a1", "b2", "c3"]))),
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(r"([a-z])(\d)".to_string()))),
+        ColumnarValue::Array(Arc::new(UInt64Array::from(vec![1, 2]))),
+    ];
+    let err = invoke_regexp_extract(&udf, args, 3).unwrap_err();
+    assert!(err.to_string().contains("Argument 3 has 2 rows but argument 1 has 3"), "{err}");
+}
+
+/// Post-processing trims and changes the case of each extracted value
+#[tokio::test]
+async fn test_regexp_extract_post_process() -> Result<()> {
+    let udf = RegexpExtractBuilder::new().post_process(PostProcess::Trim).build();
+    let ctx = ctx_with_table(&["[ abc ]", "[]"], &udf).await?;
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '\[([^\]]*)\]', 1) FROM t"#).await?;
+    assert_eq!(out.value(0), "abc");
+    assert_eq!(out.value(1), "");
+
+    let udf = RegexpExtractBuilder::new()
+        .post_process(PostProcess::Trim)
+        .post_process(PostProcess::Lowercase)
+        .build();
+    let ctx = ctx_with_table(&["[ MiXeD Ä ]"], &udf).await?;
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '\[([^\]]*)\]', 1) FROM t"#).await?;
+    assert_eq!(out.value(0), "mixed ä");
+
+    let udf = RegexpExtractBuilder::new().post_process(PostProcess::Uppercase).build();
+    let ctx = ctx_with_table(&["id: ab12"], &udf).await?;
+    let out = run_and_first_string_col(&ctx, r#"SELECT regexp_extract(s, '([a-z]+\d+)', 1) FROM t"#).await?;
+    assert_eq!(out.value(0), "AB12");
+    Ok(())
+}
+
+/// Each RegexpExtractError keeps its message through DataFusionError and can be downcast back
+#[test]
+fn test_regexp_extract_error_conversion() {
+    let unclosed = "(".to_string();
+    let errors = vec![
+        RegexpExtractError::WrongArgType { field: "regex".to_string(), got: "Int64".to_string() },
+        RegexpExtractError::InvalidPattern {
+            source: Box::new(regexp_extract::engine::Regex::new(&unclosed).unwrap_err()),
+            pattern: unclosed,
+        },
+        RegexpExtractError::GroupOutOfRange { group: 3, pattern: "(a)".to_string(), groups: 1 },
+        RegexpExtractError::NegativeGroup(-1),
+        RegexpExtractError::GroupTooLarge(u64::MAX),
+        RegexpExtractError::EmptyPattern,
+        RegexpExtractError::UnknownFlag('q'),
+        RegexpExtractError::InvalidStartPosition(0),
+        RegexpExtractError::RowCountMismatch { argument: 3, rows: 2, first: 1, first_rows: 3 },
+        RegexpExtractError::MatchTimeout { pattern: "(a+)+".to_string(), budget: std::time::Duration::from_millis(10) },
+        RegexpExtractError::MatchFailed { pattern: "(a+)+".to_string(), source: "step limit".into() },
+    ];
+    for error in errors {
+        let message = error.to_string();
+        let converted = datafusion::error::DataFusionError::from(error);
+        assert_eq!(converted.to_string(), format!("Execution error: {message}"));
+        assert_eq!(RegexpExtractError::find(&converted).map(|e| e.to_string()), Some(message));
+    }
+}
+
+/// Errors raised while running a query can be matched by kind
+#[tokio::test]
+async fn test_regexp_extract_error_from_query() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let ctx = ctx_with_table(&["abc"], &udf).await?;
+
+    let err = run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '(a)', -1) FROM t").await.unwrap_err();
+    assert!(matches!(RegexpExtractError::find(&err), Some(RegexpExtractError::NegativeGroup(-1))), "{err:?}");
+    assert!(err.to_string().starts_with("Execution error: Group index -1"), "{err}");
+
+    for (sql, expected) in [
+        ("SELECT regexp_extract(s, '', 1) FROM t", "EmptyPattern"),
+        ("SELECT regexp_extract(s, '(a)', 1, 'q') FROM t", "UnknownFlag('q')"),
+        ("SELECT regexp_extract(s, '(a)', 1, 0) FROM t", "InvalidStartPosition(0)"),
+    ] {
+        let err = run_and_first_string_col(&ctx, sql).await.unwrap_err();
+        let inner = RegexpExtractError::find(&err).unwrap();
+        assert_eq!(format!("{inner:?}"), expected, "{sql}");
+    }
+    Ok(())
+}
+
+/// The first pattern in the list that matches a row supplies the group
+#[tokio::test]
+async fn test_regexp_extract_first_of() -> Result<()> {
+    let udf = register_regexp_extract_first_of_udf();
+    let ctx = ctx_with_table(&["id 2024", "2023-07-01", "none"], &udf).await?;
+
+    let out = run_and_first_string_col(
+        &ctx,
+        r"SELECT regexp_extract_first_of(s, ['(\d{4})-\d\d', '(\d{4})'], 1) FROM t",
+    ).await?;
+    assert_eq!(out.iter().collect::<Vec<_>>(), vec![Some("2024"), Some("2023"), Some("")]);
+
+    // Only the second pattern matches "id 2024", and it has no group 2.
+    let out = run_and_first_string_col(
+        &ctx,
+        r"SELECT regexp_extract_first_of(s, ['(\d{4})-(\d\d)', '(\d{4})'], 2) FROM t",
+    ).await?;
+    assert_eq!(out.iter().collect::<Vec<_>>(), vec![None, Some("07"), Some("")]);
+    Ok(())
+}
+
+/// Expressions built with the helper get a readable column name
+#[tokio::test]
+async fn test_regexp_extract_expr_column_name() -> Result<()> {
+    let ctx = ctx_with_table(&["abc123", "x"], &register_regexp_extract_udf()).await?;
+
+    let df = ctx.table("t").await?.select(vec![regexp_extract_expr(col("s"), r"(\d+)", 1)])?;
+    assert_eq!(df.schema().field(0).name(), r"regexp_extract(t.s, '(\d+)', 1)");
+
+    let batches = df.collect().await?;
+    let out = batches[0].column(0).as_any().downcast_ref::<StringArray>().unwrap();
+    assert_eq!(out.iter().collect::<Vec<_>>(), vec![Some("123"), Some("")]);
+    Ok(())
+}
+
+/// An empty capture is a match: only a pattern that does not match at all becomes NULL
+#[tokio::test]
+async fn test_regexp_extract_empty_match_is_not_no_match() -> Result<()> {
+    let udf = register_regexp_extract_with_options(RegexpExtractOptions {
+        on_no_match: NoMatchMode::Null,
+        ..Default::default()
+    });
+    let ctx = ctx_with_table(&["bbb"], &udf).await?;
+
+    let out = run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '(a*)', 1) FROM t").await?;
+    assert!(!out.is_null(0));
+    assert_eq!(out.value(0), "");
+
+    let out = run_and_first_string_col(&ctx, "SELECT regexp_extract(s, '(a+)', 1) FROM t").await?;
+    assert!(out.is_null(0));
+    Ok(())
+}
+
+/// A zero-row batch returns an empty Utf8 array without compiling the pattern
+#[test]
+fn test_regexp_extract_empty_batch() -> Result<()> {
+    let udf = register_regexp_extract_udf();
+    let args = vec![
+        ColumnarValue::Array(Arc::new(StringArray::from(Vec::<Option<&str>>::new()))),
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(r"([a-z]+)(\d+)".to_string()))),
+        ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+    ];
+    let out = invoke_regexp_extract(&udf, args, 0)?;
+
+    let ColumnarValue::Array(out) = out else {
+        panic!("expected an array");
+    };
+    assert_eq!(out.len(), 0);
+    assert_eq!(out.data_type(), &DataType::Utf8);
+    let extract = udf.inner().as_any().downcast_ref::<RegexpExtract>().unwrap();
+    assert_eq!(extract.cache().compilations(), 0);
+    Ok(())
+}